@@ -2,6 +2,12 @@ use clap::Parser;
 use std::path::PathBuf;
 
 use crate::cli::commands::Command;
+use crate::core::scan::{ErrorPolicy, ScanOptions};
+use crate::utils::PathNormalization;
+use crate::utils::logger::LogFormat;
+use crate::utils::priority::{self, IoClass};
+use crate::utils::progress::ProgressMode;
+use crate::{config, utils};
 
 /// The command-line-interface for the application
 #[derive(Parser, Debug)]
@@ -12,6 +18,91 @@ pub struct Args {
     /// Path to the chronicle database file
     #[arg(long, global = true)]
     pub db: Option<PathBuf>,
+
+    /// Path to the chronicle configuration file
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Increase logging verbosity (-v for debug, -vv for trace)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Only log errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Format of the log messages written to stderr
+    #[arg(long, global = true, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// How to report progress of long-running scans and imports on stderr
+    #[arg(long, global = true, default_value_t = ProgressMode::Auto)]
+    pub progress: ProgressMode,
+}
+
+impl Args {
+    /// Loads the chronicle configuration file
+    pub fn load_config(&self) -> Result<config::Config, Box<dyn std::error::Error>> {
+        let config_path = utils::get_chronicle_config_path(self.config.as_ref())?;
+        config::Config::load(&config_path)
+    }
+}
+
+/// Options that control how a scan handles paths it can't read
+#[derive(clap::Args, Debug, Clone)]
+pub struct ScanErrorArgs {
+    /// What to do with files that can't be read: leave them out, abort, or keep them without a hash
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = ErrorPolicy::Record)]
+    pub on_error: ErrorPolicy,
+}
+
+/// Options that control how paths are matched between snapshots
+#[derive(clap::Args, Debug, Clone)]
+pub struct NormalizeArgs {
+    /// Match paths that only differ in Unicode normalization (nfc) or also in case (casefold)
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub normalize: Option<PathNormalization>,
+}
+
+impl NormalizeArgs {
+    /// The normalization to match paths with, falling back to the configuration of the root
+    pub fn resolve(&self, root: Option<&config::RootConfig>) -> PathNormalization {
+        self.normalize
+            .or(root.and_then(|root| root.normalize))
+            .unwrap_or_default()
+    }
+}
+
+/// Options that limit the impact of a scan on other workloads
+#[derive(clap::Args, Debug, Clone)]
+pub struct ThrottleArgs {
+    /// Maximum rate at which file contents are read (e.g. 50MiB/s)
+    #[arg(long, value_name = "RATE", value_parser = utils::parse_rate)]
+    pub max_read_rate: Option<u64>,
+
+    /// Set the CPU priority of the scan to this niceness (-20 to 19)
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = priority::parse_nice,
+        allow_negative_numbers = true
+    )]
+    pub nice: Option<i32>,
+
+    /// Lower the I/O scheduling class of the scan
+    #[arg(long, value_enum, value_name = "CLASS")]
+    pub ionice: Option<IoClass>,
+}
+
+impl ThrottleArgs {
+    /// Applies the options to the scan settings, falling back to the configuration of the root
+    pub fn apply(&self, options: &mut ScanOptions, root: Option<&config::RootConfig>) {
+        options.max_read_rate = self
+            .max_read_rate
+            .or(root.and_then(|root| root.max_read_rate));
+        options.nice = self.nice.or(root.and_then(|root| root.nice));
+        options.ionice = self.ionice.or(root.and_then(|root| root.ionice));
+    }
 }
 
 pub fn parse() -> Args {
@@ -0,0 +1,82 @@
+use clap::{Parser, ValueEnum};
+use serde_json;
+use std::path::PathBuf;
+
+use crate::{cli, core, database, utils};
+
+/// Defines the possible output formats for the collisions command.
+#[derive(ValueEnum, Clone, Debug)]
+pub enum OutputFormat {
+    Text, // Default format
+    Json,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The command to find names that collide under Unicode normalization or case folding
+#[derive(Parser, Debug)]
+pub struct Collisions {
+    /// The revision to search (e.g., a snapshot ID)
+    #[arg(default_value = "HEAD")]
+    rev: String,
+
+    /// Path (or name) of the directory the snapshot belongs to
+    #[arg(long, default_value = ".")]
+    path: PathBuf,
+
+    /// Output format
+    #[arg(long, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+impl Collisions {
+    /// Execute the collisions command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let root = core::root::resolve_path(&conn, &self.path)?;
+        let snapshot_id = match core::root::resolve(&conn, &root)? {
+            Some(root_id) => database::resolve_revision(&conn, root_id, &self.rev)?,
+            None => None,
+        }
+        .ok_or_else(|| format!("Could not find a snapshot for revision '{}'", self.rev))?;
+
+        let files = database::get_files_for_snapshot(&conn, snapshot_id)?;
+        let collisions = core::collisions::find_collisions(&files);
+
+        match self.format {
+            OutputFormat::Json => {
+                let json_output = serde_json::to_string_pretty(&collisions)?;
+                println!("{json_output}");
+            }
+            OutputFormat::Text => {
+                if collisions.is_empty() {
+                    println!("No colliding names found");
+                    return Ok(());
+                }
+
+                for collision in &collisions {
+                    println!(
+                        "{} names collide by {}",
+                        collision.paths.len(),
+                        collision.kind
+                    );
+                    for path in &collision.paths {
+                        println!("  {}", utils::display_path(path));
+                    }
+                }
+                println!("{} collisions", collisions.len());
+            }
+        }
+
+        Ok(())
+    }
+}
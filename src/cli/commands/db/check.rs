@@ -0,0 +1,97 @@
+use clap::{Parser, ValueEnum};
+
+use crate::database::{CheckResult, Severity};
+use crate::{cli, database, utils};
+
+/// Defines the possible output formats for the check command.
+#[derive(ValueEnum, Clone, Debug)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The command to check the consistency of the chronicle database
+#[derive(Parser, Debug)]
+pub struct Check {
+    /// Fix the problems that can be repaired safely
+    #[arg(long)]
+    repair: bool,
+
+    /// Output format
+    #[arg(long, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+impl Check {
+    /// Execute the check command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let mut conn = database::open(&db_path)?;
+
+        let mut results = database::check_database(&conn)?;
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+            OutputFormat::Text => print_results(&results),
+        }
+
+        if self.repair && results.iter().any(|r| r.repairable) {
+            let removed = database::repair_database(&mut conn)?;
+            log::info!("Repaired the database, removed {removed} rows");
+            results = database::check_database(&conn)?;
+        }
+
+        let errors = results
+            .iter()
+            .filter(|r| r.severity == Severity::Error)
+            .count();
+        if errors > 0 {
+            let hint = if !self.repair && results.iter().any(|r| r.repairable) {
+                ", run with --repair to fix them"
+            } else {
+                ""
+            };
+            return Err(format!("{errors} checks failed{hint}").into());
+        }
+
+        Ok(())
+    }
+}
+
+fn print_results(results: &[CheckResult]) {
+    for result in results {
+        match result.severity {
+            Severity::Ok => println!("{}: ok", result.name),
+            severity => {
+                let label = if severity == Severity::Error {
+                    "error"
+                } else {
+                    "warning"
+                };
+                let repairable = if result.repairable {
+                    ", repairable"
+                } else {
+                    ""
+                };
+                println!(
+                    "{}: {} problems ({label}{repairable})",
+                    result.name, result.count
+                );
+                for detail in &result.details {
+                    println!("  {detail}");
+                }
+                if result.count > result.details.len() {
+                    println!("  ... and {} more", result.count - result.details.len());
+                }
+            }
+        }
+    }
+}
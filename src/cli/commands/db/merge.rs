@@ -0,0 +1,33 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::{cli, database, utils};
+
+/// The command to merge another chronicle database into this one
+#[derive(Parser, Debug)]
+pub struct Merge {
+    /// Path to the other chronicle database file
+    other: PathBuf,
+}
+
+impl Merge {
+    /// Execute the merge command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let other = std::fs::canonicalize(&self.other)?;
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        if std::fs::canonicalize(&db_path).is_ok_and(|p| p == other) {
+            return Err("Cannot merge a database into itself".into());
+        }
+
+        let mut conn = database::open(&db_path)?;
+        let summary = database::merge_database(&mut conn, &other)?;
+        println!(
+            "Merged {} snapshots from {} ({} already present)",
+            summary.merged,
+            other.display(),
+            summary.skipped
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use clap::{Parser, Subcommand};
+
+use crate::cli;
+
+mod check;
+mod merge;
+mod stats;
+
+/// The command to maintain the chronicle database
+#[derive(Parser, Debug)]
+pub struct Db {
+    #[command(subcommand)]
+    command: DbCommand,
+}
+
+/// The subcommands of the db command
+#[derive(Subcommand, Debug)]
+enum DbCommand {
+    /// Merge the snapshots of another chronicle database into this one
+    Merge(merge::Merge),
+    /// Check the consistency of the database and optionally repair it
+    Check(check::Check),
+    /// Show row counts, the size of the database and the storage used per root
+    Stats(stats::Stats),
+}
+
+impl Db {
+    /// Execute the db command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        match &self.command {
+            DbCommand::Merge(cmd) => cmd.execute(cli),
+            DbCommand::Check(cmd) => cmd.execute(cli),
+            DbCommand::Stats(cmd) => cmd.execute(cli),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use clap::{Parser, ValueEnum};
+
+use crate::output_formatter::OutputFormatter;
+use crate::{cli, database, models, output_formatter, utils};
+
+/// Defines the possible output formats for the stats command.
+#[derive(ValueEnum, Clone, Debug)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The command to show storage statistics of the chronicle database
+#[derive(Parser, Debug)]
+pub struct Stats {
+    /// Output format
+    #[arg(long, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+impl Stats {
+    /// Execute the stats command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let stats = database::database_stats(&conn)?;
+
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+            OutputFormat::Text => {
+                println!("Database: {}", db_path.display());
+                println!(
+                    "Size: {} ({} reclaimable by VACUUM)",
+                    utils::format_size_auto(stats.size_bytes as u64),
+                    utils::format_size_auto(stats.free_bytes as u64)
+                );
+                println!("Roots: {}", stats.roots);
+                println!("Snapshots: {}", stats.snapshots);
+                println!("Files: {}", stats.files);
+
+                if !stats.per_root.is_empty() {
+                    println!();
+                    let headers = vec![
+                        "Path".to_string(),
+                        "Snapshots".to_string(),
+                        "Files".to_string(),
+                        "Storage".to_string(),
+                    ];
+                    let rows = stats
+                        .per_root
+                        .iter()
+                        .map(|root| {
+                            vec![
+                                utils::display_path(&root.path).into_owned(),
+                                root.snapshots.to_string(),
+                                root.files.to_string(),
+                                utils::format_size_auto(root.estimated_bytes as u64),
+                            ]
+                        })
+                        .collect();
+                    let table = models::Table::new(headers, rows);
+                    let formatter = output_formatter::TsvFormatter;
+                    println!("{}", formatter.format(&table));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
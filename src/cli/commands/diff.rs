@@ -27,52 +27,86 @@ pub struct Diff {
     /// The second revision to compare (e.g., a snapshot ID). Defaults to the latest snapshot.
     rev2: Option<String>,
 
-    /// Path to the directory the snapshots belong to
+    /// Path (or name) of the directory the snapshots belong to
     #[arg(long, default_value = ".")]
     path: PathBuf,
 
     /// Output format
     #[arg(long, default_value_t = OutputFormat::Text)]
     format: OutputFormat,
+
+    #[command(flatten)]
+    normalize: cli::args::NormalizeArgs,
 }
 
+use crate::core::scan::ScanOptions;
+use crate::utils::hashing::HashMode;
 use crate::{cli, core, database, models, utils};
 use std::path::Path;
 
+/// The files of a revision, the paths that could not be read, its display name and how it was hashed
+type FileSet = (
+    Vec<models::FileMetadata>,
+    Vec<models::ScanError>,
+    String,
+    ScanOptions,
+);
+
 impl Diff {
     /// Execute the diff command
     pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
-        let root = std::fs::canonicalize(&self.path)?;
         let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
         let conn = database::open(&db_path)?;
+        let root = core::root::resolve_path(&conn, &self.path)?;
 
         // Determine which revisions to compare based on the number of arguments
-        let (files1, name1, files2, name2) = match (&self.rev1, &self.rev2) {
+        let (set1, set2) = match (&self.rev1, &self.rev2) {
             // Case: `chronicle diff` (no args) -> compare last two snapshots
-            (None, None) => {
-                let (f1, n1) = self.resolve_revision_to_fileset(&conn, &root, Some("HEAD~1"))?;
-                let (f2, n2) = self.resolve_revision_to_fileset(&conn, &root, Some("HEAD"))?;
-                (f1, n1, f2, n2)
-            }
+            (None, None) => (
+                self.resolve_revision_to_fileset(&conn, &root, Some("HEAD~1"), None)?,
+                self.resolve_revision_to_fileset(&conn, &root, Some("HEAD"), None)?,
+            ),
             // Case: `chronicle diff <rev>` -> compare working dir vs <rev>
             (Some(r1), None) => {
-                let (f1, n1) = self.resolve_revision_to_fileset(&conn, &root, None)?; // None signifies working dir
-                let (f2, n2) = self.resolve_revision_to_fileset(&conn, &root, Some(r1.as_str()))?;
-                (f1, n1, f2, n2)
+                let set2 =
+                    self.resolve_revision_to_fileset(&conn, &root, Some(r1.as_str()), None)?;
+                // Hash the working dir like the snapshot, so that the hashes can be compared
+                let set1 =
+                    self.resolve_revision_to_fileset(&conn, &root, None, Some(set2.3.clone()))?; // None signifies working dir
+                (set1, set2)
             }
             // Case: `chronicle diff <rev1> <rev2>` -> compare two snapshots
-            (Some(r1), Some(r2)) => {
-                let (f1, n1) = self.resolve_revision_to_fileset(&conn, &root, Some(r1.as_str()))?;
-                let (f2, n2) = self.resolve_revision_to_fileset(&conn, &root, Some(r2.as_str()))?;
-                (f1, n1, f2, n2)
-            }
+            (Some(r1), Some(r2)) => (
+                self.resolve_revision_to_fileset(&conn, &root, Some(r1.as_str()), None)?,
+                self.resolve_revision_to_fileset(&conn, &root, Some(r2.as_str()), None)?,
+            ),
             // Should not be reachable with current clap config
             (None, Some(_)) => {
                 return Err("Invalid combination of arguments".into());
             }
         };
-
-        let diff = core::diff::diff_snapshots(&files1, &files2)?;
+        let ((files1, errors1, name1, options1), (files2, errors2, name2, options2)) = (set1, set2);
+
+        let compare_hashes = if options1.hash_mode == HashMode::Metadata
+            || options2.hash_mode == HashMode::Metadata
+        {
+            log::info!(
+                "{name1} or {name2} was not hashed: comparing sizes and modification times instead"
+            );
+            false
+        } else if options1.hash_algorithm != options2.hash_algorithm {
+            log::warn!(
+                "{name1} was hashed with {} and {name2} with {}: comparing sizes and modification times instead",
+                options1.hash_algorithm,
+                options2.hash_algorithm
+            );
+            false
+        } else {
+            true
+        };
+        let normalization = self.normalize.resolve(cli.load_config()?.root(&root));
+        let mut diff = core::diff::diff_snapshots(&files1, &files2, compare_hashes, normalization)?;
+        diff.account_for_errors(&errors1, &errors2);
 
         match self.format {
             OutputFormat::Json => {
@@ -84,28 +118,34 @@ impl Diff {
 
                 if diff.is_empty() {
                     println!("No changes detected.");
-                    return Ok(());
-                }
+                } else {
+                    println!("Changes detected:");
+                    if !diff.added.is_empty() {
+                        println!("\nAdded files:");
+                        for file in diff.added {
+                            println!("  + {}", utils::display_path(&file));
+                        }
+                    }
 
-                println!("Changes detected:");
-                if !diff.added.is_empty() {
-                    println!("\nAdded files:");
-                    for file in diff.added {
-                        println!("  + {file}");
+                    if !diff.removed.is_empty() {
+                        println!("\nRemoved files:");
+                        for file in diff.removed {
+                            println!("  - {}", utils::display_path(&file));
+                        }
                     }
-                }
 
-                if !diff.removed.is_empty() {
-                    println!("\nRemoved files:");
-                    for file in diff.removed {
-                        println!("  - {file}");
+                    if !diff.modified.is_empty() {
+                        println!("\nModified files:");
+                        for file in diff.modified {
+                            println!("  * {}", utils::display_path(&file));
+                        }
                     }
                 }
 
-                if !diff.modified.is_empty() {
-                    println!("\nModified files:");
-                    for file in diff.modified {
-                        println!("  * {file}");
+                if !diff.unreadable.is_empty() {
+                    println!("\nUnreadable files (changes unknown):");
+                    for file in diff.unreadable {
+                        println!("  ? {}", utils::display_path(&file));
                     }
                 }
             }
@@ -114,26 +154,34 @@ impl Diff {
         Ok(())
     }
 
-    /// Takes a revision string and resolves it to a set of files and a display name
+    /// Takes a revision string and resolves it to a set of files, the paths that could not be
+    /// read, a display name and how the files were hashed. The working directory is scanned with
+    /// `scan_options`.
     fn resolve_revision_to_fileset(
         &self,
         conn: &rusqlite::Connection,
         root: &Path,
         rev: Option<&str>,
-    ) -> Result<(Vec<models::FileMetadata>, String), Box<dyn std::error::Error>> {
+        scan_options: Option<ScanOptions>,
+    ) -> Result<FileSet, Box<dyn std::error::Error>> {
         match rev {
             // If no revision is provided, use the current working directory
             None => {
-                let files = core::scan::scan(root)?;
-                Ok((files, "current files".to_string()))
+                let options = scan_options.unwrap_or_default();
+                let scan = core::scan::scan(root, &options)?;
+                let name = "current files".to_string();
+                Ok((scan.files, scan.stats.errors, name, options))
             }
             Some(r_str) => {
-                let snapshot_id_result = if r_str.eq_ignore_ascii_case("HEAD") {
-                    database::get_latest_snapshot_id(conn, &root.to_string_lossy())
-                } else if r_str.eq_ignore_ascii_case("HEAD~1") {
-                    database::get_penultimate_snapshot_id(conn, &root.to_string_lossy())
-                } else {
-                    Ok(r_str.parse::<i64>().ok())
+                // An explicit snapshot id may belong to any root, only HEAD refers to `--path`
+                let snapshot_id_result = match r_str.parse::<i64>() {
+                    Ok(id) => {
+                        database::get_snapshot_hashing(conn, id).map(|found| found.map(|_| id))
+                    }
+                    Err(_) => match core::root::resolve(conn, root)? {
+                        Some(root_id) => database::resolve_revision(conn, root_id, r_str),
+                        None => Ok(None),
+                    },
                 };
 
                 let snapshot_id = snapshot_id_result?.ok_or_else(|| {
@@ -145,7 +193,11 @@ impl Diff {
                 })?;
 
                 let files = database::get_files_for_snapshot(conn, snapshot_id)?;
-                Ok((files, format!("snapshot {snapshot_id}")))
+                let errors = database::get_scan_stats(conn, snapshot_id)?
+                    .map(|stats| stats.errors)
+                    .unwrap_or_default();
+                let options = ScanOptions::like_snapshot(conn, snapshot_id)?;
+                Ok((files, errors, format!("snapshot {snapshot_id}"), options))
             }
         }
     }
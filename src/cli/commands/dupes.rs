@@ -0,0 +1,101 @@
+use clap::{Parser, ValueEnum};
+use serde_json;
+use std::path::PathBuf;
+
+use crate::{cli, core, database, utils};
+
+/// Defines the possible output formats for the dupes command.
+#[derive(ValueEnum, Clone, Debug)]
+pub enum OutputFormat {
+    Text, // Default format
+    Json,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The command to find duplicate files from the stored content hashes
+#[derive(Parser, Debug)]
+pub struct Dupes {
+    /// The revision to search (e.g., a snapshot ID)
+    #[arg(default_value = "HEAD", conflicts_with = "all_roots")]
+    rev: String,
+
+    /// Path (or name) of the directory the snapshot belongs to
+    #[arg(long, default_value = ".", conflicts_with = "all_roots")]
+    path: PathBuf,
+
+    /// Search the latest snapshot of every root instead
+    #[arg(long)]
+    all_roots: bool,
+
+    /// Output format
+    #[arg(long, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+impl Dupes {
+    /// Execute the dupes command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let snapshot_ids = if self.all_roots {
+            database::list_latest_snapshot_ids(&conn)?
+        } else {
+            let root = core::root::resolve_path(&conn, &self.path)?;
+            let snapshot_id = match core::root::resolve(&conn, &root)? {
+                Some(root_id) => database::resolve_revision(&conn, root_id, &self.rev)?,
+                None => None,
+            }
+            .ok_or_else(|| format!("Could not find a snapshot for revision '{}'", self.rev))?;
+            vec![snapshot_id]
+        };
+
+        let groups = database::find_duplicates(&conn, &snapshot_ids)?;
+
+        match self.format {
+            OutputFormat::Json => {
+                let json_output = serde_json::to_string_pretty(&groups)?;
+                println!("{json_output}");
+            }
+            OutputFormat::Text => {
+                if groups.is_empty() {
+                    println!("No duplicate files found");
+                    return Ok(());
+                }
+
+                for group in &groups {
+                    println!(
+                        "{} copies of {} ({} reclaimable)",
+                        group.files.len(),
+                        utils::format_size_auto(group.bytes as u64),
+                        utils::format_size_auto(group.wasted_bytes as u64)
+                    );
+                    for file in &group.files {
+                        if self.all_roots {
+                            println!("  {}", utils::display_path(&file.root.join(&file.path)));
+                        } else {
+                            println!("  {}", utils::display_path(&file.path));
+                        }
+                    }
+                }
+
+                let wasted: i64 = groups.iter().map(|group| group.wasted_bytes).sum();
+                println!(
+                    "{} groups, {} reclaimable",
+                    groups.len(),
+                    utils::format_size_auto(wasted as u64)
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::{cli, core, database, utils};
+
+/// The command to export snapshots into a portable bundle
+#[derive(Parser, Debug)]
+pub struct Export {
+    /// Path of the bundle file to write
+    output: PathBuf,
+
+    /// Path (or name) of the directory whose snapshots should be exported. Exports every root if omitted.
+    #[arg(long)]
+    path: Option<PathBuf>,
+
+    /// Only export these revisions (e.g., a snapshot ID, HEAD or HEAD~1)
+    #[arg(long, num_args = 1..)]
+    revs: Vec<String>,
+}
+
+impl Export {
+    /// Execute the export command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        // Revisions are relative to a root, which defaults to the current directory
+        let root = match (&self.path, self.revs.is_empty()) {
+            (Some(path), _) => Some(core::root::resolve_path(&conn, path)?),
+            (None, false) => Some(std::fs::canonicalize(".")?),
+            (None, true) => None,
+        };
+        let root_id =
+            match &root {
+                Some(root) => Some(core::root::resolve(&conn, root)?.ok_or_else(|| {
+                    format!("No snapshots found for directory: {}", root.display())
+                })?),
+                None => None,
+            };
+
+        let snapshot_ids = if self.revs.is_empty() {
+            database::list_snapshot_ids(&conn, root_id)?
+        } else {
+            let root_id = root_id.unwrap_or_default();
+            self.revs
+                .iter()
+                .map(|rev| {
+                    database::resolve_revision(&conn, root_id, rev)?.ok_or_else(|| {
+                        format!("Could not find a snapshot for revision '{rev}'").into()
+                    })
+                })
+                .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?
+        };
+
+        if snapshot_ids.is_empty() {
+            return Err("No snapshots to export".into());
+        }
+
+        let file = std::fs::File::create(&self.output)?;
+        let count = core::bundle::export(&conn, &snapshot_ids, std::io::BufWriter::new(file))?;
+        println!("Exported {count} snapshots to {}", self.output.display());
+
+        Ok(())
+    }
+}
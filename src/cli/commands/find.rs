@@ -0,0 +1,129 @@
+use chrono::{DateTime, Local};
+use clap::{ArgGroup, Parser, ValueEnum};
+use serde_json;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::database::FileFilter;
+use crate::output_formatter::OutputFormatter;
+use crate::utils::hashing::Throttle;
+use crate::{cli, database, models, output_formatter, utils};
+
+/// Defines the possible output formats for the find command.
+#[derive(ValueEnum, Clone, Debug)]
+pub enum OutputFormat {
+    Tsv,
+    Json,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Tsv => write!(f, "tsv"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The command to find which snapshots and roots contain a file
+#[derive(Parser, Debug)]
+#[command(group(ArgGroup::new("filter").required(true).args(["hash", "name", "file"])))]
+pub struct Find {
+    /// Find files whose content hash starts with this prefix
+    #[arg(long)]
+    hash: Option<String>,
+
+    /// Find files whose name matches this glob (e.g. "*.jpg")
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Find files with the same content as this local file
+    #[arg(long)]
+    file: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, default_value_t = OutputFormat::Tsv)]
+    format: OutputFormat,
+}
+
+impl Find {
+    /// Execute the find command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let occurrences = match (&self.hash, &self.name, &self.file) {
+            (Some(prefix), _, _) => {
+                database::find_files(&conn, &FileFilter::HashPrefix(prefix.clone()))?
+            }
+            (_, Some(glob), _) => database::find_files(&conn, &FileFilter::Name(glob.clone()))?,
+            // Snapshots may use different algorithms, so hash the file with each of them
+            (_, _, Some(file)) => {
+                let mut occurrences = Vec::new();
+                for algorithm in database::list_hash_algorithms(&conn)? {
+                    let hash =
+                        utils::hashing::hash_file(file, algorithm, &mut Throttle::unlimited())?
+                            .hash;
+                    occurrences.extend(
+                        database::find_files(&conn, &FileFilter::HashPrefix(hash))?
+                            .into_iter()
+                            .filter(|occurrence| occurrence.hash_algorithm == algorithm),
+                    );
+                }
+                occurrences
+            }
+            (None, None, None) => return Err("Nothing to search for".into()),
+        };
+
+        if occurrences.is_empty() {
+            println!("No matching files found");
+            return Ok(());
+        }
+
+        match self.format {
+            OutputFormat::Json => {
+                let json_output = serde_json::to_string_pretty(&occurrences)?;
+                println!("{json_output}");
+            }
+            OutputFormat::Tsv => {
+                let headers = vec![
+                    "Root".to_string(),
+                    "Path".to_string(),
+                    "Hash".to_string(),
+                    "Size".to_string(),
+                    "First".to_string(),
+                    "Last".to_string(),
+                    "Commit".to_string(),
+                ];
+
+                let format_seen = |id: i64, time: SystemTime| {
+                    let datetime: DateTime<Local> = time.into();
+                    format!("{id} ({})", datetime.format("%Y-%m-%d %H:%M:%S"))
+                };
+
+                let mut rows = Vec::new();
+                for occurrence in occurrences {
+                    let mut hash = occurrence.content_hash.unwrap_or_else(|| "-".to_string());
+                    hash.truncate(12);
+                    rows.push(vec![
+                        utils::display_path(&occurrence.root).into_owned(),
+                        utils::display_path(&occurrence.path).into_owned(),
+                        hash,
+                        utils::format_size_auto(occurrence.bytes as u64),
+                        format_seen(occurrence.first_snapshot, occurrence.first_seen),
+                        format_seen(occurrence.last_snapshot, occurrence.last_seen),
+                        occurrence
+                            .git_commit_hash
+                            .unwrap_or_else(|| "-".to_string()),
+                    ]);
+                }
+
+                let table = models::Table::new(headers, rows);
+                let formatter = output_formatter::TsvFormatter;
+                println!("{}", formatter.format(&table));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,12 +1,12 @@
 use clap::Parser;
 use std::path::PathBuf;
 
-use crate::{cli, core};
+use crate::{cli, core, database, utils};
 
 /// The command to manage Git repository synchronization
 #[derive(Parser, Debug)]
 pub struct Git {
-    /// Path to the Git repository to synchronize
+    /// Path (or name) of the Git repository to synchronize
     #[arg(default_value = ".")]
     path: PathBuf,
 }
@@ -14,7 +14,10 @@ pub struct Git {
 impl Git {
     /// Execute the command to manage Git repository synchronization
     pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
-        println!("Synchronizing Git history from: {}", self.path.display());
-        core::git_sync::sync_history(&self.path, cli.db.as_ref())
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let path = core::root::resolve_path(&database::open(&db_path)?, &self.path)?;
+        log::info!("Synchronizing Git history from: {}", path.display());
+        core::git_sync::sync_history(&path, cli.db.as_ref())?;
+        Ok(())
     }
 }
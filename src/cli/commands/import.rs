@@ -0,0 +1,32 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::{cli, core, database, utils};
+
+/// The command to import snapshots from a bundle
+#[derive(Parser, Debug)]
+pub struct Import {
+    /// Path of the bundle file to read
+    bundle: PathBuf,
+
+    /// Rewrite snapshot roots starting with OLD to start with NEW instead (e.g., --root-map /mnt/a=/media/b)
+    #[arg(long, value_name = "OLD=NEW", value_parser = core::bundle::parse_root_mapping)]
+    root_map: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Import {
+    /// Execute the import command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let mut conn = database::open(&db_path)?;
+
+        let file = std::fs::File::open(&self.bundle)?;
+        let summary = core::bundle::import(&mut conn, file, &self.root_map)?;
+        println!(
+            "Imported {} snapshots ({} already present)",
+            summary.imported, summary.skipped
+        );
+
+        Ok(())
+    }
+}
@@ -4,7 +4,7 @@ use serde_json;
 use std::path::PathBuf;
 
 use crate::output_formatter::OutputFormatter;
-use crate::{cli, database, models, output_formatter, utils};
+use crate::{cli, core, database, models, output_formatter, utils};
 
 /// Defines the possible output formats for the list command.
 #[derive(ValueEnum, Clone, Debug)]
@@ -22,10 +22,13 @@ impl std::fmt::Display for OutputFormat {
     }
 }
 
-/// The command to list all snapshots for a given directory
+/// The command to list all snapshots for a given directory.
+///
+/// With the global `--verbose` flag, the statistics of the scan each snapshot was taken with
+/// are listed as well.
 #[derive(Parser, Debug)]
 pub struct List {
-    /// Path to the directory to list snapshots for
+    /// Path (or name) of the directory to list snapshots for
     #[arg(default_value = ".")]
     path: PathBuf,
 
@@ -37,40 +40,73 @@ pub struct List {
 impl List {
     /// Execute the command to list all snapshots for a given directory
     pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
-        let root = std::fs::canonicalize(&self.path)?;
-
         let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
         let conn = database::open(&db_path)?;
 
-        let snapshots = database::list_snapshots_for_root(&conn, &root.to_string_lossy())?;
+        let root = core::root::resolve_path(&conn, &self.path)?;
+
+        let mut snapshots = match core::root::resolve(&conn, &root)? {
+            Some(root_id) => database::list_snapshots_for_root(&conn, root_id)?,
+            None => Vec::new(),
+        };
 
         if snapshots.is_empty() {
             println!("No snapshots found for directory: {}", root.display());
             return Ok(());
         }
 
+        let verbose = cli.verbose > 0;
+        if verbose {
+            for snapshot in &mut snapshots {
+                snapshot.scan_stats = database::get_scan_stats(&conn, snapshot.id)?;
+            }
+        }
+
         match self.format {
             OutputFormat::Json => {
                 let json_output = serde_json::to_string_pretty(&snapshots)?;
                 println!("{json_output}");
             }
             OutputFormat::Tsv => {
-                let headers = vec![
+                let mut headers = vec![
                     "ID".to_string(),
                     "Timestamp".to_string(),
                     "Files".to_string(),
                     "Size".to_string(),
+                    "Disk usage".to_string(),
                 ];
+                if verbose {
+                    headers.extend(
+                        [
+                            "Duration",
+                            "Visited",
+                            "Ignored",
+                            "Unreadable",
+                            "Hashed",
+                            "Rate",
+                            "Atime preserved",
+                        ]
+                        .map(String::from),
+                    );
+                }
 
                 let mut rows = Vec::new();
                 for snapshot in snapshots {
                     let datetime: DateTime<Local> = snapshot.timestamp.into();
-                    rows.push(vec![
+                    let mut row = vec![
                         snapshot.id.to_string(),
                         datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
                         snapshot.file_count.to_string(),
                         utils::format_size_auto(snapshot.total_size as u64),
-                    ]);
+                        snapshot
+                            .disk_usage
+                            .map(|bytes| utils::format_size_auto(bytes as u64))
+                            .unwrap_or_else(|| "-".to_string()),
+                    ];
+                    if verbose {
+                        row.extend(stats_columns(&snapshot));
+                    }
+                    rows.push(row);
                 }
 
                 let table = models::Table::new(headers, rows);
@@ -82,3 +118,29 @@ impl List {
         Ok(())
     }
 }
+
+/// The scan statistics columns of a snapshot, dashes if they were not recorded
+fn stats_columns(snapshot: &models::SnapshotMetadata) -> Vec<String> {
+    let atime = if snapshot.atime_preserved {
+        "yes"
+    } else {
+        "no"
+    }
+    .to_string();
+    match &snapshot.scan_stats {
+        Some(stats) => vec![
+            format!("{:.1}s", stats.duration.as_secs_f64()),
+            stats.files_visited.to_string(),
+            stats.paths_ignored.to_string(),
+            stats.errors.len().to_string(),
+            utils::format_size_auto(stats.bytes_hashed),
+            format!("{}/s", utils::format_size_auto(stats.read_rate())),
+            atime,
+        ],
+        None => {
+            let mut columns = vec!["-".to_string(); 6];
+            columns.push(atime);
+            columns
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Local};
+use clap::Parser;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::core::tree::DirNode;
+use crate::{cli, core, database, utils};
+
+/// The command to list the files of a directory as recorded in a snapshot
+#[derive(Parser, Debug)]
+pub struct Ls {
+    /// Subdirectory to list, relative to the root
+    subdir: Option<PathBuf>,
+
+    /// Show the directory as of this time (e.g. "2025-03-01" or "2025-03-01 12:30"),
+    /// using the last snapshot taken at or before it. Defaults to the latest snapshot.
+    #[arg(long)]
+    at: Option<String>,
+
+    /// Path (or name) of the root the snapshots belong to
+    #[arg(long, default_value = ".")]
+    path: PathBuf,
+
+    /// Show sizes, file counts and modification times
+    #[arg(short = 'l', long = "long")]
+    long: bool,
+
+    /// Show the whole tree below the directory
+    #[arg(long)]
+    tree: bool,
+}
+
+impl Ls {
+    /// Execute the ls command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let root = core::root::resolve_path(&conn, &self.path)?;
+        let root_id = core::root::resolve(&conn, &root)?
+            .ok_or_else(|| format!("No snapshots found for directory: {}", root.display()))?;
+
+        let snapshot_id = match &self.at {
+            Some(at) => {
+                let timestamp = utils::parse_timestamp(at)
+                    .ok_or_else(|| format!("Invalid time '{at}', expected e.g. 2025-03-01"))?;
+                database::resolve_timestamp(&conn, root_id, timestamp)?
+                    .ok_or_else(|| format!("No snapshot of {} at or before {at}", root.display()))?
+            }
+            None => database::get_latest_snapshot_id(&conn, root_id)?
+                .ok_or_else(|| format!("No snapshots found for directory: {}", root.display()))?,
+        };
+        log::info!("Listing snapshot {snapshot_id}");
+
+        let files = database::get_files_for_snapshot(&conn, snapshot_id)?;
+        let tree = DirNode::build(&files);
+        let subdir = self.subdir.clone().unwrap_or_default();
+        let dir = tree.find(&subdir).ok_or_else(|| {
+            format!(
+                "No such directory in snapshot {snapshot_id}: {}",
+                subdir.display()
+            )
+        })?;
+
+        if self.tree {
+            self.print_tree(dir, "");
+        } else {
+            self.print_dir(dir);
+        }
+
+        Ok(())
+    }
+
+    /// Prints the entries of a directory, subdirectories first
+    fn print_dir(&self, dir: &DirNode) {
+        for (name, subdir) in &dir.dirs {
+            if self.long {
+                println!(
+                    "{:>12}  {:>8}  {}  {name}/",
+                    utils::format_size_auto(subdir.total_bytes()),
+                    subdir.file_count(),
+                    format_time(subdir.last_modified())
+                );
+            } else {
+                println!("{name}/");
+            }
+        }
+        for (name, file) in &dir.files {
+            if self.long {
+                println!(
+                    "{:>12}  {:>8}  {}  {name}",
+                    utils::format_size_auto(file.bytes),
+                    "-",
+                    format_time(file.modified_at)
+                );
+            } else {
+                println!("{name}");
+            }
+        }
+    }
+
+    /// Prints a directory recursively, with box-drawing branches
+    fn print_tree(&self, dir: &DirNode, prefix: &str) {
+        let count = dir.dirs.len() + dir.files.len();
+        let entries = dir
+            .dirs
+            .iter()
+            .map(|(name, subdir)| (name, Some(subdir), subdir.total_bytes()))
+            .chain(
+                dir.files
+                    .iter()
+                    .map(|(name, file)| (name, None, file.bytes)),
+            );
+
+        for (index, (name, subdir, bytes)) in entries.enumerate() {
+            let last = index + 1 == count;
+            let branch = if last { "└── " } else { "├── " };
+            let slash = if subdir.is_some() { "/" } else { "" };
+            if self.long {
+                println!(
+                    "{prefix}{branch}{name}{slash} ({})",
+                    utils::format_size_auto(bytes)
+                );
+            } else {
+                println!("{prefix}{branch}{name}{slash}");
+            }
+            if let Some(subdir) = subdir {
+                let continuation = if last { "    " } else { "│   " };
+                self.print_tree(subdir, &format!("{prefix}{continuation}"));
+            }
+        }
+    }
+}
+
+fn format_time(time: Option<SystemTime>) -> String {
+    time.map(|t| {
+        let datetime: DateTime<Local> = t.into();
+        datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+    })
+    .unwrap_or_else(|| "-".repeat(19))
+}
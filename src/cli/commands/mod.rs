@@ -1,10 +1,25 @@
 use clap::Subcommand;
 
+mod collisions;
+mod db;
 mod diff;
+mod dupes;
+mod export;
+mod find;
 mod git;
+mod import;
 mod list;
+mod ls;
+mod query;
+mod rm;
+mod root;
+mod roots;
 mod snapshot;
 mod status;
+mod track;
+mod undo;
+mod untrack;
+mod verify;
 
 /// The subcommands of the command-line-interface
 #[derive(Subcommand, Debug)]
@@ -17,6 +32,9 @@ pub enum Command {
     #[command(alias = "log")]
     List(list::List),
 
+    /// List a directory as recorded in a snapshot, optionally as of a past time
+    Ls(ls::Ls),
+
     /// Show the difference between the current directory state and the last snapshot
     #[command(alias = "st")]
     Status(status::Status),
@@ -24,6 +42,48 @@ pub enum Command {
     /// Show the difference between snapshots or the current state
     Diff(diff::Diff),
 
+    /// Find duplicate files using the stored content hashes
+    Dupes(dupes::Dupes),
+
+    /// Find names that collide under Unicode normalization or case folding
+    Collisions(collisions::Collisions),
+
+    /// Find which snapshots and roots contain a file
+    Find(find::Find),
+
+    /// Filter the files of a snapshot with a query
+    Query(query::Query),
+
     /// Synchronize Git history into chronicle
     Git(git::Git),
+
+    /// Export snapshots into a portable bundle
+    Export(export::Export),
+
+    /// Import snapshots from a bundle
+    Import(import::Import),
+
+    /// Maintain the chronicle database
+    Db(db::Db),
+
+    /// Manage the identity of tracked roots
+    Root(root::Root),
+
+    /// List every tracked root
+    Roots(roots::Roots),
+
+    /// Register a root, optionally with a friendly name
+    Track(track::Track),
+
+    /// Unregister a root and drop its name
+    Untrack(untrack::Untrack),
+
+    /// Delete snapshots
+    Rm(rm::Rm),
+
+    /// Delete the latest snapshot
+    Undo(undo::Undo),
+
+    /// Verify the current files against a snapshot to detect bit rot
+    Verify(verify::Verify),
 }
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Local};
+use clap::{Parser, ValueEnum};
+use serde_json;
+use std::path::PathBuf;
+
+use crate::output_formatter::OutputFormatter;
+use crate::{cli, core, database, models, output_formatter, utils};
+
+/// Defines the possible output formats for the query command.
+#[derive(ValueEnum, Clone, Debug)]
+pub enum OutputFormat {
+    Tsv,
+    Json,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Tsv => write!(f, "tsv"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The command to filter the files of a snapshot with a query
+#[derive(Parser, Debug)]
+pub struct Query {
+    /// The filter, e.g. 'ext = "mp4" and bytes > 1GiB and modified < 2024-01-01'
+    expr: String,
+
+    /// The revision to query (e.g., a snapshot ID)
+    #[arg(long, default_value = "HEAD")]
+    rev: String,
+
+    /// Path (or name) of the directory the snapshot belongs to
+    #[arg(long, default_value = ".")]
+    path: PathBuf,
+
+    /// Output format
+    #[arg(long, default_value_t = OutputFormat::Tsv)]
+    format: OutputFormat,
+}
+
+impl Query {
+    /// Execute the query command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        // Reject invalid queries before touching the database
+        let filter = core::query::compile(&self.expr)?;
+
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let root = core::root::resolve_path(&conn, &self.path)?;
+        let snapshot_id = match core::root::resolve(&conn, &root)? {
+            Some(root_id) => database::resolve_revision(&conn, root_id, &self.rev)?,
+            None => None,
+        }
+        .ok_or_else(|| format!("Could not find a snapshot for revision '{}'", self.rev))?;
+
+        let files = database::query_files(&conn, snapshot_id, &filter.sql, &filter.params)?;
+
+        match self.format {
+            OutputFormat::Json => {
+                let json_output = serde_json::to_string_pretty(&files)?;
+                println!("{json_output}");
+            }
+            OutputFormat::Tsv => {
+                let headers = vec![
+                    "Path".to_string(),
+                    "Size".to_string(),
+                    "Modified".to_string(),
+                    "Hash".to_string(),
+                ];
+
+                let mut rows = Vec::new();
+                for file in files {
+                    let modified = file
+                        .modified_at
+                        .map(|t| {
+                            let datetime: DateTime<Local> = t.into();
+                            datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+                        })
+                        .unwrap_or_else(|| "-".to_string());
+                    rows.push(vec![
+                        utils::display_path(&file.path).into_owned(),
+                        utils::format_size_auto(file.bytes),
+                        modified,
+                        file.content_hash.unwrap_or_else(|| "-".to_string()),
+                    ]);
+                }
+
+                let table = models::Table::new(headers, rows);
+                let formatter = output_formatter::TsvFormatter;
+                println!("{}", formatter.format(&table));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::{DateTime, Local};
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::{cli, core, database, models, utils};
+
+/// The command to delete snapshots
+#[derive(Parser, Debug)]
+pub struct Rm {
+    /// The revisions to delete (e.g., a snapshot ID, HEAD or HEAD~1)
+    #[arg(required = true)]
+    revs: Vec<String>,
+
+    /// Path (or name) of the directory the snapshots belong to
+    #[arg(long, default_value = ".")]
+    path: PathBuf,
+
+    /// Don't ask for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+impl Rm {
+    /// Execute the rm command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let mut conn = database::open(&db_path)?;
+
+        let root = core::root::resolve_path(&conn, &self.path)?;
+        let root_id = core::root::resolve(&conn, &root)?
+            .ok_or_else(|| format!("No snapshots found for directory: {}", root.display()))?;
+
+        let mut snapshot_ids = Vec::new();
+        for rev in &self.revs {
+            let id = database::resolve_revision(&conn, root_id, rev)?
+                .ok_or_else(|| format!("Could not find a snapshot for revision '{rev}'"))?;
+            if !snapshot_ids.contains(&id) {
+                snapshot_ids.push(id);
+            }
+        }
+
+        let snapshots: Vec<_> = database::list_snapshots_for_root(&conn, root_id)?
+            .into_iter()
+            .filter(|s| snapshot_ids.contains(&s.id))
+            .collect();
+        delete_with_confirmation(&mut conn, &root, &snapshots, self.yes)
+    }
+}
+
+/// Lists the snapshots about to be deleted, asks for confirmation and deletes them
+pub fn delete_with_confirmation(
+    conn: &mut rusqlite::Connection,
+    root: &std::path::Path,
+    snapshots: &[models::SnapshotMetadata],
+    yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    eprintln!(
+        "The following snapshots of {} will be deleted:",
+        root.display()
+    );
+    for snapshot in snapshots {
+        let datetime: DateTime<Local> = snapshot.timestamp.into();
+        eprintln!(
+            "  {}\t{}\t{} files\t{}",
+            snapshot.id,
+            datetime.format("%Y-%m-%d %H:%M:%S"),
+            snapshot.file_count,
+            utils::format_size_auto(snapshot.total_size as u64)
+        );
+    }
+
+    if !yes && !utils::confirm("Delete these snapshots?")? {
+        println!("Aborted, nothing was deleted");
+        return Ok(());
+    }
+
+    let ids: Vec<i64> = snapshots.iter().map(|s| s.id).collect();
+    let deleted = database::delete_snapshots(conn, &ids)?;
+    println!("Deleted {deleted} snapshots");
+    Ok(())
+}
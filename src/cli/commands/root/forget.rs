@@ -0,0 +1,44 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::{cli, core, database, utils};
+
+/// The command to forget a root and delete its entire history
+#[derive(Parser, Debug)]
+pub struct Forget {
+    /// Path (or name) of the root. The path does not need to exist anymore.
+    path: PathBuf,
+
+    /// Don't ask for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+impl Forget {
+    /// Execute the forget command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let mut conn = database::open(&db_path)?;
+
+        let root = match core::root::resolve_path(&conn, &self.path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => std::path::absolute(&self.path)?,
+            root => root?,
+        };
+        let root_id = database::find_root_id(&conn, &root)?
+            .ok_or_else(|| format!("{} is not a tracked root", root.display()))?;
+
+        let snapshots = database::list_snapshot_ids(&conn, Some(root_id))?.len();
+        let question = format!(
+            "Forget {} and delete its {snapshots} snapshots?",
+            root.display()
+        );
+        if !self.yes && !utils::confirm(&question)? {
+            println!("Aborted, nothing was deleted");
+            return Ok(());
+        }
+
+        let deleted = database::forget_root(&mut conn, root_id)?;
+        println!("Forgot {} and deleted {deleted} snapshots", root.display());
+        Ok(())
+    }
+}
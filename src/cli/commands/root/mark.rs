@@ -0,0 +1,35 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::{cli, core, database, utils};
+
+/// The command to write the identity marker of a root
+#[derive(Parser, Debug)]
+pub struct Mark {
+    /// Path (or name) of the root directory
+    #[arg(default_value = ".")]
+    path: PathBuf,
+}
+
+impl Mark {
+    /// Execute the mark command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+        let root = core::root::resolve_path(&conn, &self.path)?;
+
+        let root_id = match core::root::resolve(&conn, &root)? {
+            Some(id) => id,
+            None => database::ensure_root(&conn, &root)?,
+        };
+        let uuid = database::get_root_uuid(&conn, root_id)?;
+        core::root::write_marker(&root, &uuid)?;
+
+        println!(
+            "Marked {} as root {uuid} ({})",
+            root.display(),
+            core::root::MARKER_FILE
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use clap::{Parser, Subcommand};
+
+use crate::cli;
+
+mod forget;
+mod mark;
+mod r#move;
+
+/// The command to manage the identity of tracked roots
+#[derive(Parser, Debug)]
+pub struct Root {
+    #[command(subcommand)]
+    command: RootCommand,
+}
+
+/// The subcommands of the root command
+#[derive(Subcommand, Debug)]
+enum RootCommand {
+    /// Write a marker file so that the root is recognized after being moved or remounted
+    Mark(mark::Mark),
+
+    /// Point the history of a root at its new location
+    #[command(alias = "mv")]
+    Move(r#move::Move),
+
+    /// Forget a root and delete its entire history
+    Forget(forget::Forget),
+}
+
+impl Root {
+    /// Execute the root command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        match &self.command {
+            RootCommand::Mark(cmd) => cmd.execute(cli),
+            RootCommand::Move(cmd) => cmd.execute(cli),
+            RootCommand::Forget(cmd) => cmd.execute(cli),
+        }
+    }
+}
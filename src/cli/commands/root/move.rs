@@ -0,0 +1,43 @@
+use clap::Parser;
+use std::path::{Path, PathBuf};
+
+use crate::{cli, core, database, utils};
+
+/// The command to point the history of a root at its new location
+#[derive(Parser, Debug)]
+pub struct Move {
+    /// The path (or name) the root was tracked under. The path does not need to exist anymore.
+    old: PathBuf,
+
+    /// The new path of the root
+    new: PathBuf,
+}
+
+impl Move {
+    /// Execute the move command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let old = match core::root::resolve_path(&conn, &self.old) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => absolute(&self.old)?,
+            old => old?,
+        };
+        let new = absolute(&self.new)?;
+
+        let root_id = database::find_root_id(&conn, &old)?
+            .ok_or_else(|| format!("{} is not a tracked root", old.display()))?;
+        if database::find_root_id(&conn, &new)?.is_some() {
+            return Err(format!("{} is already a tracked root", new.display()).into());
+        }
+
+        database::set_root_path(&conn, root_id, &new)?;
+        println!("Moved root {} to {}", old.display(), new.display());
+        Ok(())
+    }
+}
+
+/// Canonicalizes the path if it exists, otherwise makes it absolute
+fn absolute(path: &Path) -> std::io::Result<PathBuf> {
+    std::fs::canonicalize(path).or_else(|_| std::path::absolute(path))
+}
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Local};
+use clap::{Parser, ValueEnum};
+use serde_json;
+use std::time::SystemTime;
+
+use crate::output_formatter::OutputFormatter;
+use crate::{cli, database, models, output_formatter, utils};
+
+/// Defines the possible output formats for the roots command.
+#[derive(ValueEnum, Clone, Debug)]
+pub enum OutputFormat {
+    Tsv,
+    Json,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Tsv => write!(f, "tsv"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The command to list every tracked root
+#[derive(Parser, Debug)]
+pub struct Roots {
+    /// Output format
+    #[arg(long, default_value_t = OutputFormat::Tsv)]
+    format: OutputFormat,
+}
+
+impl Roots {
+    /// Execute the command to list every tracked root
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let roots = database::list_roots(&conn)?;
+
+        if roots.is_empty() {
+            println!("No roots are tracked yet");
+            return Ok(());
+        }
+
+        match self.format {
+            OutputFormat::Json => {
+                let json_output = serde_json::to_string_pretty(&roots)?;
+                println!("{json_output}");
+            }
+            OutputFormat::Tsv => {
+                let headers = vec![
+                    "ID".to_string(),
+                    "Name".to_string(),
+                    "Path".to_string(),
+                    "Source".to_string(),
+                    "Snapshots".to_string(),
+                    "First".to_string(),
+                    "Last".to_string(),
+                    "Files".to_string(),
+                    "Size".to_string(),
+                    "Disk usage".to_string(),
+                ];
+
+                let format_time = |time: Option<SystemTime>| {
+                    time.map(|t| {
+                        let datetime: DateTime<Local> = t.into();
+                        datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+                    })
+                    .unwrap_or_else(|| "-".to_string())
+                };
+
+                let mut rows = Vec::new();
+                for root in roots {
+                    rows.push(vec![
+                        root.id.to_string(),
+                        root.name.unwrap_or_else(|| "-".to_string()),
+                        utils::display_path(&root.path).into_owned(),
+                        root.source.to_string(),
+                        root.snapshot_count.to_string(),
+                        format_time(root.first_snapshot),
+                        format_time(root.last_snapshot),
+                        root.file_count.to_string(),
+                        utils::format_size_auto(root.total_size as u64),
+                        root.disk_usage
+                            .map(|bytes| utils::format_size_auto(bytes as u64))
+                            .unwrap_or_else(|| "-".to_string()),
+                    ]);
+                }
+
+                let table = models::Table::new(headers, rows);
+                let formatter = output_formatter::TsvFormatter;
+                println!("{}", formatter.format(&table));
+            }
+        }
+
+        Ok(())
+    }
+}
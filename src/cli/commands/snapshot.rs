@@ -1,19 +1,244 @@
 use clap::Parser;
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::{cli, core};
+use crate::core::scan::ScanOptions;
+use crate::core::snapshot::SnapshotOutcome;
+use crate::output_formatter::OutputFormatter;
+use crate::utils::hashing::{HashAlgorithm, HashMode};
+use crate::{cli, config, core, database, models, output_formatter, utils};
 
 /// The command to scan a directory and record a snapshot
 #[derive(Parser, Debug)]
 pub struct Snapshot {
-    /// Path to the directory to scan
-    #[arg(default_value = ".")]
+    /// Path (or name) of the directory to scan
+    #[arg(default_value = ".", conflicts_with = "all")]
     path: PathBuf,
+
+    /// Snapshot every known root: those already in the database and those listed in the configuration
+    #[arg(long)]
+    all: bool,
+
+    /// Number of roots to snapshot in parallel (with --all)
+    #[arg(short, long, default_value_t = 1, requires = "all", value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: u16,
+
+    /// Algorithm used to hash file contents (Git history is always hashed with BLAKE3)
+    #[arg(long = "hash", value_enum, default_value_t = HashAlgorithm::Blake3)]
+    hash_algorithm: HashAlgorithm,
+
+    /// Don't hash file contents, detect changes from sizes and modification times only
+    #[arg(long, conflicts_with = "hash_algorithm")]
+    no_hash: bool,
+
+    /// Only hash the size and the first, middle and last 4 MiB of files larger than SIZE (e.g. 1GiB)
+    #[arg(long, value_name = "SIZE", value_parser = utils::parse_size, conflicts_with = "no_hash")]
+    sample_above: Option<u64>,
+
+    /// Hash every file in full, ignoring the sampling configured for the root, and replace sampled hashes
+    #[arg(long, conflicts_with_all = ["no_hash", "sample_above"])]
+    deep: bool,
+
+    #[command(flatten)]
+    throttle: cli::args::ThrottleArgs,
+
+    #[command(flatten)]
+    errors: cli::args::ScanErrorArgs,
+
+    #[command(flatten)]
+    normalize: cli::args::NormalizeArgs,
 }
 
 impl Snapshot {
+    /// The scan settings for a root, from the command line and the root's configuration
+    fn scan_options(&self, root: &Path, config: &config::Config) -> ScanOptions {
+        let root_config = config.root(root);
+        let sample_above = self
+            .sample_above
+            .or_else(|| root_config.and_then(|root| root.sample_above));
+        let hash_mode = if self.no_hash {
+            HashMode::Metadata
+        } else if let (false, Some(above)) = (self.deep, sample_above) {
+            HashMode::Sampled { above }
+        } else {
+            HashMode::Full
+        };
+        let mut options = ScanOptions {
+            hash_algorithm: self.hash_algorithm,
+            hash_mode,
+            on_error: self.errors.on_error,
+            normalization: self.normalize.resolve(root_config),
+            ..Default::default()
+        };
+        self.throttle.apply(&mut options, root_config);
+        options
+    }
+
     /// Execute the command to scan a directory and record a snapshot
     pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
-        core::snapshot::take_snapshot(&self.path, cli.db.as_ref())
+        if self.all {
+            return self.execute_all(cli);
+        }
+
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let path = core::root::resolve_path(&database::open(&db_path)?, &self.path)?;
+        let config = cli.load_config()?;
+        let options = self.scan_options(&path, &config);
+        match core::snapshot::take_snapshot(&path, cli.db.as_ref(), &options)? {
+            SnapshotOutcome::Scanned {
+                snapshot_id: None, ..
+            } => println!("No changes detected"),
+            SnapshotOutcome::Scanned {
+                diff,
+                snapshot_id: Some(snapshot_id),
+            } if diff.is_empty() => {
+                println!("No changes detected, sampled hashes upgraded");
+                println!("Snapshot stored with id {snapshot_id}");
+            }
+            SnapshotOutcome::Scanned {
+                diff,
+                snapshot_id: Some(snapshot_id),
+            } => {
+                println!("Snapshot detected changes:");
+                if !diff.added.is_empty() {
+                    println!("  + {} added files", diff.added.len());
+                }
+                if !diff.removed.is_empty() {
+                    println!("  - {} removed files", diff.removed.len());
+                }
+                if !diff.modified.is_empty() {
+                    println!("  * {} modified files", diff.modified.len());
+                }
+                if !diff.unreadable.is_empty() {
+                    println!("  ? {} unreadable files", diff.unreadable.len());
+                }
+                println!("Snapshot stored with id {snapshot_id}");
+            }
+            SnapshotOutcome::Synced { imported } => {
+                println!("Imported {imported} commits");
+            }
+        }
+
+        Ok(())
     }
+
+    /// Snapshot every known root, isolating failures so that one root can't abort the others
+    fn execute_all(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let config = cli.load_config()?;
+        let roots = known_roots(cli, &config)?;
+        if roots.is_empty() {
+            println!("No roots are known yet");
+            return Ok(());
+        }
+
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(roots.len()));
+        let workers = (self.jobs as usize).min(roots.len());
+        // The progress of concurrent roots would interleave and couldn't be told apart
+        if workers > 1 {
+            utils::progress::disable();
+        }
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(root) = roots.get(i) else {
+                            break;
+                        };
+                        let result = core::snapshot::take_snapshot(
+                            root,
+                            cli.db.as_ref(),
+                            &self.scan_options(root, &config),
+                        )
+                        .map_err(|e| e.to_string());
+                        if let Err(e) = &result {
+                            log::error!("Failed to snapshot {}: {e}", root.display());
+                        }
+                        results.lock().unwrap().push((i, result));
+                    }
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(i, _)| *i);
+
+        let headers = vec![
+            "Root".to_string(),
+            "Status".to_string(),
+            "Added".to_string(),
+            "Removed".to_string(),
+            "Modified".to_string(),
+            "Snapshot".to_string(),
+            "Error".to_string(),
+        ];
+
+        let dash = || "-".to_string();
+        let mut failed = 0;
+        let mut rows = Vec::new();
+        for (i, result) in results {
+            let root = roots[i].display().to_string();
+            rows.push(match result {
+                Ok(SnapshotOutcome::Scanned { diff, snapshot_id }) => vec![
+                    root,
+                    if snapshot_id.is_some() {
+                        "changed"
+                    } else {
+                        "unchanged"
+                    }
+                    .to_string(),
+                    diff.added.len().to_string(),
+                    diff.removed.len().to_string(),
+                    diff.modified.len().to_string(),
+                    snapshot_id.map(|id| id.to_string()).unwrap_or_else(dash),
+                    dash(),
+                ],
+                Ok(SnapshotOutcome::Synced { imported }) => vec![
+                    root,
+                    format!("synced {imported} commits"),
+                    dash(),
+                    dash(),
+                    dash(),
+                    dash(),
+                    dash(),
+                ],
+                Err(e) => {
+                    failed += 1;
+                    vec![root, "error".to_string(), dash(), dash(), dash(), dash(), e]
+                }
+            });
+        }
+
+        let table = models::Table::new(headers, rows);
+        let formatter = output_formatter::TsvFormatter;
+        println!("{}", formatter.format(&table));
+
+        if failed > 0 {
+            return Err(format!("{failed} of {} roots failed", roots.len()).into());
+        }
+        Ok(())
+    }
+}
+
+/// Collects the roots present in the database and those listed in the configuration
+fn known_roots(
+    cli: &cli::args::Args,
+    config: &config::Config,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+    let conn = database::open(&db_path)?;
+
+    let mut roots: BTreeSet<PathBuf> = database::list_roots(&conn)?
+        .into_iter()
+        .map(|root| root.path)
+        .collect();
+    for root in &config.roots {
+        roots.insert(std::fs::canonicalize(&root.path).unwrap_or(root.path.clone()));
+    }
+
+    Ok(roots.into_iter().collect())
 }
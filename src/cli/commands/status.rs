@@ -2,8 +2,8 @@ use clap::{Parser, ValueEnum};
 use serde_json;
 use std::path::PathBuf;
 
-use crate::core::scan;
-use crate::{cli, database, utils};
+use crate::core::scan::{self, ScanOptions};
+use crate::{cli, core, database, utils};
 
 /// Defines the possible output formats for the status command.
 #[derive(ValueEnum, Clone, Debug)]
@@ -24,28 +24,73 @@ impl std::fmt::Display for OutputFormat {
 /// The command to show the difference between the current directory state and the last snapshot
 #[derive(Parser, Debug)]
 pub struct Status {
-    /// Path to the directory to diff
+    /// Path (or name) of the directory to diff
     #[arg(default_value = ".")]
     path: PathBuf,
 
     /// Output format
     #[arg(long, default_value_t = OutputFormat::Text)]
     format: OutputFormat,
+
+    #[command(flatten)]
+    throttle: cli::args::ThrottleArgs,
+
+    #[command(flatten)]
+    errors: cli::args::ScanErrorArgs,
+
+    #[command(flatten)]
+    normalize: cli::args::NormalizeArgs,
 }
 
 impl Status {
     /// Execute the status command
     pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
-        let root = std::fs::canonicalize(&self.path)?;
-
         let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
-        let mut conn = database::open(&db_path)?;
+        let conn = database::open(&db_path)?;
+
+        let root = core::root::resolve_path(&conn, &self.path)?;
+
+        // Recognize the root if it was moved or remounted since the last snapshot
+        let root_id = core::root::resolve(&conn, &root)?;
+
+        // Hash the files like the last snapshot, so that the hashes can be compared
+        let last_id = match root_id {
+            Some(root_id) => database::get_latest_snapshot_id(&conn, root_id)?,
+            None => None,
+        };
+        let mut options = match last_id {
+            Some(id) => ScanOptions::like_snapshot(&conn, id)?,
+            None => ScanOptions::default(),
+        };
+        let config = cli.load_config()?;
+        self.throttle.apply(&mut options, config.root(&root));
+        options.on_error = self.errors.on_error;
+        options.normalization = self.normalize.resolve(config.root(&root));
+
+        // Changes to the paths the last snapshot couldn't read can't be told apart from errors
+        if let Some(id) = last_id
+            && let Some(stats) = database::get_scan_stats(&conn, id)?
+            && !stats.is_complete()
+        {
+            log::warn!(
+                "Snapshot {id} is incomplete: {} paths could not be read, changes to them may be misreported",
+                stats.errors.len()
+            );
+        }
 
         // Get current files metadata
-        let current_files = scan::scan(&root)?;
+        let current = scan::scan(&root, &options)?;
 
         // Compute the diff against the last snapshot
-        let diff = database::compute_diff(&mut conn, &root.to_string_lossy(), &current_files)?;
+        let diff = database::compute_diff(
+            &conn,
+            root_id,
+            &current.files,
+            &current.stats.errors,
+            options.hash_algorithm,
+            options.hash_mode,
+            options.normalization,
+        )?;
 
         match self.format {
             OutputFormat::Json => {
@@ -57,28 +102,34 @@ impl Status {
 
                 if diff.is_empty() {
                     println!("No changes detected since last snapshot.");
-                    return Ok(());
-                }
+                } else {
+                    println!("Changes detected:");
+                    if !diff.added.is_empty() {
+                        println!("\nAdded files:");
+                        for file in diff.added {
+                            println!("  + {}", utils::display_path(&file));
+                        }
+                    }
 
-                println!("Changes detected:");
-                if !diff.added.is_empty() {
-                    println!("\nAdded files:");
-                    for file in diff.added {
-                        println!("  + {file}");
+                    if !diff.removed.is_empty() {
+                        println!("\nRemoved files:");
+                        for file in diff.removed {
+                            println!("  - {}", utils::display_path(&file));
+                        }
                     }
-                }
 
-                if !diff.removed.is_empty() {
-                    println!("\nRemoved files:");
-                    for file in diff.removed {
-                        println!("  - {file}");
+                    if !diff.modified.is_empty() {
+                        println!("\nModified files:");
+                        for file in diff.modified {
+                            println!("  * {}", utils::display_path(&file));
+                        }
                     }
                 }
 
-                if !diff.modified.is_empty() {
-                    println!("\nModified files:");
-                    for file in diff.modified {
-                        println!("  * {file}");
+                if !diff.unreadable.is_empty() {
+                    println!("\nUnreadable files (changes unknown):");
+                    for file in diff.unreadable {
+                        println!("  ? {}", utils::display_path(&file));
                     }
                 }
             }
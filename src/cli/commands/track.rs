@@ -0,0 +1,62 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::{cli, core, database, utils};
+
+/// The command to register a root, optionally with a friendly name
+#[derive(Parser, Debug)]
+pub struct Track {
+    /// Path to the directory to track
+    #[arg(default_value = ".")]
+    path: PathBuf,
+
+    /// A friendly name that can be used instead of the path in other commands
+    #[arg(long, value_parser = parse_name)]
+    name: Option<String>,
+}
+
+impl Track {
+    /// Execute the track command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let root = core::root::resolve_path(&conn, &self.path)?;
+        if !root.is_dir() {
+            return Err(format!("{} is not a directory", root.display()).into());
+        }
+
+        if let Some(name) = &self.name
+            && let Some((_, path)) = database::find_root_by_name(&conn, name)?
+            && path != root
+        {
+            return Err(format!(
+                "The name '{name}' is already used by {}",
+                utils::display_path(&path)
+            )
+            .into());
+        }
+
+        let root_id = match core::root::resolve(&conn, &root)? {
+            Some(id) => id,
+            None => database::ensure_root(&conn, &root)?,
+        };
+        database::track_root(&conn, root_id, self.name.as_deref())?;
+
+        match &self.name {
+            Some(name) => println!("Tracking {} as '{name}'", root.display()),
+            None => println!("Tracking {}", root.display()),
+        }
+        Ok(())
+    }
+}
+
+/// Validates a root name. Names must not look like paths, so they can't be confused with one.
+fn parse_name(s: &str) -> Result<String, String> {
+    if s.is_empty() || s == "." || s == ".." || s.contains(['/', '\\']) {
+        return Err(format!(
+            "Invalid name '{s}', names must not contain path separators"
+        ));
+    }
+    Ok(s.to_string())
+}
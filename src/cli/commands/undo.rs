@@ -0,0 +1,39 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::{cli, core, database, utils};
+
+/// The command to delete the latest snapshot
+#[derive(Parser, Debug)]
+pub struct Undo {
+    /// Path (or name) of the directory whose latest snapshot should be deleted
+    #[arg(default_value = ".")]
+    path: PathBuf,
+
+    /// Don't ask for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+impl Undo {
+    /// Execute the undo command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let mut conn = database::open(&db_path)?;
+
+        let root = core::root::resolve_path(&conn, &self.path)?;
+        let root_id = core::root::resolve(&conn, &root)?
+            .ok_or_else(|| format!("No snapshots found for directory: {}", root.display()))?;
+
+        // Snapshots are listed newest first
+        let latest = database::list_snapshots_for_root(&conn, root_id)?
+            .into_iter()
+            .take(1)
+            .collect::<Vec<_>>();
+        if latest.is_empty() {
+            return Err(format!("No snapshots found for directory: {}", root.display()).into());
+        }
+
+        super::rm::delete_with_confirmation(&mut conn, &root, &latest, self.yes)
+    }
+}
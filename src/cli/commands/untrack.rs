@@ -0,0 +1,30 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::{cli, core, database, utils};
+
+/// The command to unregister a root and drop its name
+#[derive(Parser, Debug)]
+pub struct Untrack {
+    /// Path (or name) of the directory to stop tracking
+    path: PathBuf,
+}
+
+impl Untrack {
+    /// Execute the untrack command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let root = core::root::resolve_path(&conn, &self.path)?;
+        let root_id = database::find_root_id(&conn, &root)?
+            .ok_or_else(|| format!("{} is not a tracked root", root.display()))?;
+
+        database::untrack_root(&conn, root_id)?;
+        println!(
+            "Stopped tracking {}. Existing snapshots are kept.",
+            root.display()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use clap::{Parser, ValueEnum};
+use serde_json;
+use std::path::PathBuf;
+
+use crate::core::scan::ScanOptions;
+use crate::models::{IssueKind, Severity};
+use crate::utils::hashing::HashMode;
+use crate::{cli, core, database, utils};
+
+/// Defines the possible output formats for the verify command.
+#[derive(ValueEnum, Clone, Debug)]
+pub enum OutputFormat {
+    Text, // Default format
+    Json,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The command to verify the live files against a snapshot to detect bit rot
+#[derive(Parser, Debug)]
+pub struct Verify {
+    /// The revision to verify against (e.g., a snapshot ID)
+    #[arg(default_value = "HEAD")]
+    rev: String,
+
+    /// Path (or name) of the directory to verify
+    #[arg(long, default_value = ".")]
+    path: PathBuf,
+
+    /// Output format
+    #[arg(long, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+impl Verify {
+    /// Execute the verify command
+    pub fn execute(&self, cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
+        let db_path = utils::get_chronicle_db_path(cli.db.as_ref())?;
+        let conn = database::open(&db_path)?;
+
+        let root = core::root::resolve_path(&conn, &self.path)?;
+        let snapshot_id = match core::root::resolve(&conn, &root)? {
+            Some(root_id) => database::resolve_revision(&conn, root_id, &self.rev)?,
+            None => None,
+        }
+        .ok_or_else(|| format!("Could not find a snapshot for revision '{}'", self.rev))?;
+
+        // Hash the live files like the snapshot, so that the hashes can be compared
+        let options = ScanOptions::like_snapshot(&conn, snapshot_id)?;
+        if options.hash_mode == HashMode::Metadata {
+            return Err(format!(
+                "Snapshot {snapshot_id} was taken without hashing, its contents can't be verified"
+            )
+            .into());
+        }
+        let stored = database::get_files_for_snapshot(&conn, snapshot_id)?;
+        let live = core::scan::scan(&root, &options)?.files;
+        let report = core::verify::verify(&live, &stored);
+
+        match self.format {
+            OutputFormat::Json => {
+                let json_output = serde_json::to_string_pretty(&report)?;
+                println!("{json_output}");
+            }
+            OutputFormat::Text => {
+                println!(
+                    "Verifying {} against snapshot {snapshot_id}",
+                    root.display()
+                );
+                for issue in &report.issues {
+                    let description = match issue.kind {
+                        IssueKind::Corrupted => "content changed, size and mtime unchanged",
+                        IssueKind::Unreadable => "unreadable",
+                        IssueKind::Missing => "missing",
+                    };
+                    println!(
+                        "  [{}] {}: {description}",
+                        issue.severity,
+                        utils::display_path(&issue.path)
+                    );
+                }
+                println!(
+                    "{} verified, {} modified, {} added, {} without stored hash, {} issues",
+                    report.verified,
+                    report.modified,
+                    report.added,
+                    report.skipped,
+                    report.issues.len()
+                );
+            }
+        }
+
+        // Fail on possible bit rot, so that monitoring can alert on the exit status
+        if report.max_severity() == Some(Severity::Critical) {
+            let corrupted = report
+                .issues
+                .iter()
+                .filter(|issue| issue.severity == Severity::Critical)
+                .count();
+            return Err(format!("{corrupted} files show signs of bit rot").into());
+        }
+
+        Ok(())
+    }
+}
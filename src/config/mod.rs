@@ -0,0 +1,211 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::utils;
+use crate::utils::PathNormalization;
+use crate::utils::priority::{self, IoClass};
+
+/// The user configuration, read from `config.toml` in the chronicle directory.
+///
+/// ```toml
+/// [[roots]]
+/// path = "/mnt/archive/photos"
+///
+/// [[roots]]
+/// path = "/mnt/images"
+/// sample_above = "1GiB"
+/// max_read_rate = "50MiB/s"
+/// nice = 10
+/// ionice = "idle"
+/// ```
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Roots that should be snapshotted by `chronicle snapshot --all`
+    #[serde(default)]
+    pub roots: Vec<RootConfig>,
+}
+
+/// The configuration of a single root
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RootConfig {
+    /// Path to the root directory
+    pub path: PathBuf,
+    /// Files larger than this many bytes are only sampled when hashing (e.g. `"1GiB"`)
+    #[serde(default, deserialize_with = "deserialize_size")]
+    pub sample_above: Option<u64>,
+    /// Maximum rate at which file contents are read when scanning (e.g. `"50MiB/s"`)
+    #[serde(default, deserialize_with = "deserialize_rate")]
+    pub max_read_rate: Option<u64>,
+    /// Niceness scans of the root run at (-20 to 19)
+    #[serde(default, deserialize_with = "deserialize_nice")]
+    pub nice: Option<i32>,
+    /// I/O scheduling class scans of the root are lowered to
+    pub ionice: Option<IoClass>,
+    /// How paths are matched when the root is compared with its snapshots
+    pub normalize: Option<PathNormalization>,
+}
+
+impl Config {
+    /// Loads the configuration file. A missing file yields the default configuration.
+    pub fn load(path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e.into()),
+        };
+        toml::from_str(&content)
+            .map_err(|e| format!("Invalid configuration in {}: {e}", path.display()).into())
+    }
+
+    /// The configuration of the root at the given (canonical) path, if any
+    pub fn root(&self, path: &Path) -> Option<&RootConfig> {
+        self.roots
+            .iter()
+            .find(|root| std::fs::canonicalize(&root.path).unwrap_or(root.path.clone()) == path)
+    }
+}
+
+/// Reads a size given either as a number of bytes or as a string with a unit (e.g. `"500MB"`)
+fn deserialize_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserialize_quantity(deserializer, utils::parse_size)
+}
+
+/// Reads a rate given either as a number of bytes per second or as a string (e.g. `"50MiB/s"`)
+fn deserialize_rate<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserialize_quantity(deserializer, utils::parse_rate)
+}
+
+/// Reads a niceness, checking that it is in range
+fn deserialize_nice<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let nice = i32::deserialize(deserializer)?;
+    priority::check_nice(nice)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn deserialize_quantity<'de, D>(
+    deserializer: D,
+    parse: fn(&str) -> Result<u64, String>,
+) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+
+    match Size::deserialize(deserializer)? {
+        Size::Bytes(bytes) => Ok(Some(bytes)),
+        Size::Text(text) => parse(&text).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::load(&dir.path().join("config.toml")).unwrap();
+        assert!(config.roots.is_empty());
+    }
+
+    #[test]
+    fn test_load_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[[roots]]\npath = \"/mnt/a\"\n\n[[roots]]\npath = \"/mnt/b\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        let paths: Vec<_> = config.roots.iter().map(|r| r.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("/mnt/a"), PathBuf::from("/mnt/b")]
+        );
+    }
+
+    #[test]
+    fn test_load_sample_above() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[[roots]]\npath = \"/mnt/a\"\nsample_above = \"1GiB\"\n\n[[roots]]\npath = \"/mnt/b\"\nsample_above = 1000\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.roots[0].sample_above, Some(1 << 30));
+        assert_eq!(config.roots[1].sample_above, Some(1000));
+        assert!(config.root(Path::new("/mnt/b")).is_some());
+
+        std::fs::write(
+            &path,
+            "[[roots]]\npath = \"/mnt/a\"\nsample_above = \"big\"\n",
+        )
+        .unwrap();
+        assert!(Config::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_load_throttling() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[[roots]]\npath = \"/mnt/a\"\nmax_read_rate = \"50MiB/s\"\nnice = 10\nionice = \"best-effort\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.roots[0].max_read_rate, Some(50 * 1024 * 1024));
+        assert_eq!(config.roots[0].nice, Some(10));
+        assert_eq!(config.roots[0].ionice, Some(IoClass::BestEffort));
+
+        std::fs::write(&path, "[[roots]]\npath = \"/mnt/a\"\nnice = -5\n").unwrap();
+        assert_eq!(Config::load(&path).unwrap().roots[0].nice, Some(-5));
+        std::fs::write(&path, "[[roots]]\npath = \"/mnt/a\"\nnice = 20\n").unwrap();
+        assert!(Config::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_load_normalization() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[[roots]]\npath = \"/mnt/a\"\nnormalize = \"casefold\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.roots[0].normalize, Some(PathNormalization::Casefold));
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[[roots]]\npaht = \"/mnt/a\"\n").unwrap();
+        assert!(Config::load(&path).is_err());
+    }
+}
@@ -0,0 +1,554 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::hashing::{HashAlgorithm, HashMode};
+use crate::utils::{
+    clamp_nanos, deserialize_path, display_path, from_nanos, serialize_path, to_nanos,
+};
+use crate::{database, models};
+
+/// Identifies a file as a chronicle bundle
+const BUNDLE_FORMAT: &str = "chronicle-bundle";
+
+/// Version of the bundle format. Bumped whenever the records change incompatibly
+pub const BUNDLE_VERSION: u32 = 3;
+
+/// The magic bytes at the start of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// A single line of a bundle.
+///
+/// A bundle is a (gzip-compressed) NDJSON stream that starts with a `header` record,
+/// followed by each `snapshot` record and the `file` records that belong to it.
+/// Timestamps are stored as nanoseconds since the Unix epoch, exactly as in the database.
+///
+/// Version 2 added the hash algorithm to snapshots; version 1 snapshots are hashed with BLAKE3.
+/// Snapshots without a hash mode were fully hashed. Before version 3, timestamps were seconds.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record {
+    Header {
+        format: String,
+        version: u32,
+    },
+    Snapshot {
+        #[serde(
+            serialize_with = "serialize_path",
+            deserialize_with = "deserialize_path"
+        )]
+        root: PathBuf,
+        timestamp: i64,
+        git_commit_hash: Option<String>,
+        #[serde(default)]
+        hash_algorithm: HashAlgorithm,
+        #[serde(default)]
+        hash_mode: HashMode,
+        #[serde(default)]
+        atime_preserved: bool,
+        #[serde(default)]
+        scan_stats: Option<models::ScanStats>,
+        file_count: usize,
+    },
+    File {
+        #[serde(
+            serialize_with = "serialize_path",
+            deserialize_with = "deserialize_path"
+        )]
+        path: PathBuf,
+        bytes: u64,
+        modified_at: Option<i64>,
+        created_at: Option<i64>,
+        accessed_at: Option<i64>,
+        content_hash: Option<String>,
+        #[serde(default)]
+        hash_sampled: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        disk_usage: Option<models::DiskUsage>,
+    },
+}
+
+/// The outcome of importing a bundle
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Number of snapshots inserted into the database
+    pub imported: usize,
+    /// Number of snapshots that were already present and skipped
+    pub skipped: usize,
+}
+
+/// Writes the given snapshots as a gzip-compressed bundle. Returns the number of snapshots written.
+pub fn export(
+    conn: &Connection,
+    snapshot_ids: &[i64],
+    writer: impl Write,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+    write_records(conn, snapshot_ids, &mut encoder)?;
+    encoder.finish()?.flush()?;
+    Ok(snapshot_ids.len())
+}
+
+fn write_records(
+    conn: &Connection,
+    snapshot_ids: &[i64],
+    writer: &mut impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write_record(
+        writer,
+        &Record::Header {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+        },
+    )?;
+
+    for &snapshot_id in snapshot_ids {
+        let snapshot = database::get_snapshot(conn, snapshot_id)?
+            .ok_or_else(|| format!("Snapshot {snapshot_id} does not exist"))?;
+
+        write_record(
+            writer,
+            &Record::Snapshot {
+                root: snapshot.root,
+                timestamp: clamp_nanos(snapshot.timestamp),
+                git_commit_hash: snapshot.git_commit_hash,
+                hash_algorithm: snapshot.hash_algorithm,
+                hash_mode: snapshot.hash_mode,
+                atime_preserved: snapshot.atime_preserved,
+                scan_stats: snapshot.scan_stats,
+                file_count: snapshot.files.len(),
+            },
+        )?;
+
+        for file in snapshot.files {
+            write_record(
+                writer,
+                &Record::File {
+                    path: file.path,
+                    bytes: file.bytes,
+                    modified_at: file.modified_at.and_then(to_nanos),
+                    created_at: file.created_at.and_then(to_nanos),
+                    accessed_at: file.accessed_at.and_then(to_nanos),
+                    content_hash: file.content_hash,
+                    hash_sampled: file.hash_sampled,
+                    disk_usage: file.disk_usage,
+                },
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_record(
+    writer: &mut impl Write,
+    record: &Record,
+) -> Result<(), Box<dyn std::error::Error>> {
+    serde_json::to_writer(&mut *writer, record)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Reads a bundle (compressed or plain NDJSON) and inserts its snapshots into the database.
+///
+/// Snapshots already present (same root, timestamp and Git commit) are skipped, so importing
+/// the same bundle twice is harmless. Roots are rewritten according to `root_map`, where the
+/// first matching `(old, new)` prefix wins.
+pub fn import(
+    conn: &mut Connection,
+    reader: impl Read,
+    root_map: &[(PathBuf, PathBuf)],
+) -> Result<ImportSummary, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(reader);
+    let is_gzip = reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+    if is_gzip {
+        let decoder = flate2::bufread::GzDecoder::new(reader);
+        read_records(conn, BufReader::new(decoder), root_map)
+    } else {
+        read_records(conn, reader, root_map)
+    }
+}
+
+fn read_records(
+    conn: &mut Connection,
+    reader: impl BufRead,
+    root_map: &[(PathBuf, PathBuf)],
+) -> Result<ImportSummary, Box<dyn std::error::Error>> {
+    let mut lines = reader.lines();
+
+    // Validate the header
+    let header = lines.next().ok_or("The bundle is empty")??;
+    let from_time: fn(i64) -> SystemTime = match serde_json::from_str(&header)? {
+        Record::Header { format, version } if format == BUNDLE_FORMAT => {
+            if version > BUNDLE_VERSION {
+                return Err(format!(
+                    "Unsupported bundle version {version} (this build supports up to {BUNDLE_VERSION})"
+                )
+                .into());
+            }
+            if version < 3 { from_secs } else { from_nanos }
+        }
+        _ => return Err("Not a chronicle bundle: missing header".into()),
+    };
+
+    let mut summary = ImportSummary::default();
+    let mut pending: Option<(models::Snapshot, usize)> = None;
+
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str(&line)? {
+            Record::Header { .. } => {
+                return Err("Unexpected header in the middle of the bundle".into());
+            }
+            Record::Snapshot {
+                root,
+                timestamp,
+                git_commit_hash,
+                hash_algorithm,
+                hash_mode,
+                atime_preserved,
+                scan_stats,
+                file_count,
+            } => {
+                if let Some((snapshot, expected)) = pending.take() {
+                    store(conn, snapshot, expected, &mut summary)?;
+                }
+                let snapshot = models::Snapshot {
+                    root: map_root(&root, root_map),
+                    timestamp: from_time(timestamp),
+                    git_commit_hash,
+                    hash_algorithm,
+                    hash_mode,
+                    atime_preserved,
+                    scan_stats,
+                    files: Vec::with_capacity(file_count),
+                };
+                pending = Some((snapshot, file_count));
+            }
+            Record::File {
+                path,
+                bytes,
+                modified_at,
+                created_at,
+                accessed_at,
+                content_hash,
+                hash_sampled,
+                disk_usage,
+            } => {
+                let (snapshot, _) = pending
+                    .as_mut()
+                    .ok_or("File record found before any snapshot record")?;
+                snapshot.files.push(models::FileMetadata {
+                    path,
+                    bytes,
+                    modified_at: modified_at.map(from_time),
+                    created_at: created_at.map(from_time),
+                    accessed_at: accessed_at.map(from_time),
+                    content_hash,
+                    hash_sampled,
+                    disk_usage,
+                });
+            }
+        }
+    }
+
+    if let Some((snapshot, expected)) = pending.take() {
+        store(conn, snapshot, expected, &mut summary)?;
+    }
+
+    Ok(summary)
+}
+
+/// Inserts a single imported snapshot, unless it is already present
+fn store(
+    conn: &mut Connection,
+    snapshot: models::Snapshot,
+    expected_files: usize,
+    summary: &mut ImportSummary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if snapshot.files.len() != expected_files {
+        return Err(format!(
+            "Truncated bundle: snapshot of {} at {} has {} of {expected_files} files",
+            display_path(&snapshot.root),
+            clamp_nanos(snapshot.timestamp),
+            snapshot.files.len()
+        )
+        .into());
+    }
+
+    let existing = match database::find_root_id(conn, &snapshot.root)? {
+        Some(root_id) => database::find_snapshot(
+            conn,
+            root_id,
+            clamp_nanos(snapshot.timestamp),
+            snapshot.git_commit_hash.as_deref(),
+        )?,
+        None => None,
+    };
+    if let Some(id) = existing {
+        log::debug!(
+            "Skipping snapshot of {} already present as {id}",
+            display_path(&snapshot.root)
+        );
+        summary.skipped += 1;
+        return Ok(());
+    }
+
+    let id = database::insert_snapshot(conn, &snapshot)?;
+    log::debug!(
+        "Imported snapshot of {} as {id}",
+        display_path(&snapshot.root)
+    );
+    summary.imported += 1;
+    Ok(())
+}
+
+/// Rewrites the root using the first matching `(old, new)` prefix mapping
+fn map_root(root: &Path, root_map: &[(PathBuf, PathBuf)]) -> PathBuf {
+    for (old, new) in root_map {
+        if let Ok(rest) = root.strip_prefix(old) {
+            return if rest.as_os_str().is_empty() {
+                new.clone()
+            } else {
+                new.join(rest)
+            };
+        }
+    }
+    root.to_path_buf()
+}
+
+/// Parses a `--root-map` argument of the form `old=new`
+pub fn parse_root_mapping(s: &str) -> Result<(PathBuf, PathBuf), String> {
+    let (old, new) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid root mapping '{s}', expected OLD=NEW"))?;
+    if old.is_empty() || new.is_empty() {
+        return Err(format!("Invalid root mapping '{s}', expected OLD=NEW"));
+    }
+    Ok((PathBuf::from(old), PathBuf::from(new)))
+}
+
+fn from_secs(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{get_files_for_snapshot, initialize_schema, insert_snapshot};
+    use crate::models::{FileMetadata, Snapshot};
+
+    fn create_in_memory_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        conn
+    }
+
+    fn sample_snapshot(root: &str, timestamp: u64) -> Snapshot {
+        Snapshot {
+            root: PathBuf::from(root),
+            timestamp: UNIX_EPOCH + Duration::from_secs(timestamp),
+            git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
+            files: vec![
+                FileMetadata {
+                    path: PathBuf::from("a.txt"),
+                    bytes: 1,
+                    modified_at: Some(UNIX_EPOCH + Duration::from_secs(100)),
+                    created_at: None,
+                    accessed_at: Some(UNIX_EPOCH + Duration::new(200, 123_456_789)),
+                    content_hash: Some("hash_a".to_string()),
+                    hash_sampled: false,
+                    disk_usage: Some(models::DiskUsage {
+                        allocated_bytes: 4096,
+                        device: 1,
+                        inode: 2,
+                    }),
+                },
+                FileMetadata {
+                    path: PathBuf::from("dir/b.txt"),
+                    bytes: 2,
+                    modified_at: None,
+                    created_at: None,
+                    accessed_at: None,
+                    content_hash: None,
+                    hash_sampled: false,
+                    disk_usage: None,
+                },
+            ],
+        }
+    }
+
+    fn sorted_files(conn: &Connection, snapshot_id: i64) -> Vec<(String, u64, Option<String>)> {
+        let mut files: Vec<_> = get_files_for_snapshot(conn, snapshot_id)
+            .unwrap()
+            .into_iter()
+            .map(|f| {
+                (
+                    f.path.to_string_lossy().to_string(),
+                    f.bytes,
+                    f.content_hash,
+                )
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut source = create_in_memory_db();
+        let id1 = insert_snapshot(&mut source, &sample_snapshot("/data", 1_000)).unwrap();
+        let mut snapshot = sample_snapshot("/data", 2_000);
+        snapshot.hash_algorithm = HashAlgorithm::Sha256;
+        let id2 = insert_snapshot(&mut source, &snapshot).unwrap();
+
+        let mut bundle = Vec::new();
+        assert_eq!(export(&source, &[id1, id2], &mut bundle).unwrap(), 2);
+
+        let mut target = create_in_memory_db();
+        let summary = import(&mut target, bundle.as_slice(), &[]).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 2,
+                skipped: 0
+            }
+        );
+
+        for id in [id1, id2] {
+            let original = database::get_snapshot(&source, id).unwrap().unwrap();
+            let imported = database::get_snapshot(&target, id).unwrap().unwrap();
+            assert_eq!(original.root, imported.root);
+            assert_eq!(original.timestamp, imported.timestamp);
+            assert_eq!(original.hash_algorithm, imported.hash_algorithm);
+            assert_eq!(sorted_files(&source, id), sorted_files(&target, id));
+            assert_eq!(original.files[0].accessed_at, imported.files[0].accessed_at);
+            assert_eq!(original.files[0].disk_usage, imported.files[0].disk_usage);
+        }
+    }
+
+    #[test]
+    fn test_import_is_idempotent() {
+        let mut source = create_in_memory_db();
+        let id = insert_snapshot(&mut source, &sample_snapshot("/data", 1_000)).unwrap();
+        let mut bundle = Vec::new();
+        export(&source, &[id], &mut bundle).unwrap();
+
+        let mut target = create_in_memory_db();
+        import(&mut target, bundle.as_slice(), &[]).unwrap();
+        let summary = import(&mut target, bundle.as_slice(), &[]).unwrap();
+
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 0,
+                skipped: 1
+            }
+        );
+        assert_eq!(database::list_snapshot_ids(&target, None).unwrap().len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_round_trip_non_utf8_root() {
+        let root = crate::utils::path_from_bytes(b"/mnt/caf\xe9".to_vec());
+        let mut snapshot = sample_snapshot("/", 1_000);
+        snapshot.root = root.clone();
+        let mut source = create_in_memory_db();
+        let id = insert_snapshot(&mut source, &snapshot).unwrap();
+        let mut bundle = Vec::new();
+        export(&source, &[id], &mut bundle).unwrap();
+
+        let mut target = create_in_memory_db();
+        let summary = import(&mut target, bundle.as_slice(), &[]).unwrap();
+        assert_eq!(summary.imported, 1);
+        let root_id = database::find_root_id(&target, &root).unwrap().unwrap();
+        let ids = database::list_snapshot_ids(&target, Some(root_id)).unwrap();
+        assert_eq!(
+            database::get_snapshot(&target, ids[0])
+                .unwrap()
+                .unwrap()
+                .root,
+            root
+        );
+
+        // Importing again finds the root, and skips the snapshot
+        let summary = import(&mut target, bundle.as_slice(), &[]).unwrap();
+        assert_eq!(summary.skipped, 1);
+    }
+
+    #[test]
+    fn test_import_with_root_map() {
+        let mut source = create_in_memory_db();
+        let id = insert_snapshot(&mut source, &sample_snapshot("/mnt/old/photos", 1_000)).unwrap();
+        let mut bundle = Vec::new();
+        export(&source, &[id], &mut bundle).unwrap();
+
+        let mut target = create_in_memory_db();
+        let root_map = vec![parse_root_mapping("/mnt/old=/media/new").unwrap()];
+        import(&mut target, bundle.as_slice(), &root_map).unwrap();
+
+        let root_id = database::find_root_id(&target, Path::new("/media/new/photos"))
+            .unwrap()
+            .unwrap();
+        let ids = database::list_snapshot_ids(&target, Some(root_id)).unwrap();
+        assert_eq!(ids.len(), 1);
+        assert_eq!(sorted_files(&target, ids[0]), sorted_files(&source, id));
+    }
+
+    #[test]
+    fn test_import_rejects_truncated_bundle() {
+        let bundle = concat!(
+            "{\"type\":\"header\",\"format\":\"chronicle-bundle\",\"version\":1}\n",
+            "{\"type\":\"snapshot\",\"root\":\"/data\",\"timestamp\":1,\"git_commit_hash\":null,\"file_count\":2}\n",
+            "{\"type\":\"file\",\"path\":\"a\",\"bytes\":1,\"modified_at\":null,\"created_at\":null,\"accessed_at\":null,\"content_hash\":null}\n",
+        );
+        let mut target = create_in_memory_db();
+        assert!(import(&mut target, bundle.as_bytes(), &[]).is_err());
+        assert!(
+            database::list_snapshot_ids(&target, None)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_import_version_2_seconds() {
+        let bundle = concat!(
+            "{\"type\":\"header\",\"format\":\"chronicle-bundle\",\"version\":2}\n",
+            "{\"type\":\"snapshot\",\"root\":\"/data\",\"timestamp\":1000,\"git_commit_hash\":null,\"file_count\":1}\n",
+            "{\"type\":\"file\",\"path\":\"a\",\"bytes\":1,\"modified_at\":100,\"created_at\":null,\"accessed_at\":null,\"content_hash\":null}\n",
+        );
+        let mut target = create_in_memory_db();
+        import(&mut target, bundle.as_bytes(), &[]).unwrap();
+
+        let ids = database::list_snapshot_ids(&target, None).unwrap();
+        let snapshot = database::get_snapshot(&target, ids[0]).unwrap().unwrap();
+        assert_eq!(snapshot.timestamp, UNIX_EPOCH + Duration::from_secs(1000));
+        assert_eq!(
+            snapshot.files[0].modified_at,
+            Some(UNIX_EPOCH + Duration::from_secs(100))
+        );
+    }
+
+    #[test]
+    fn test_parse_root_mapping() {
+        assert_eq!(
+            parse_root_mapping("/a=/b").unwrap(),
+            (PathBuf::from("/a"), PathBuf::from("/b"))
+        );
+        assert!(parse_root_mapping("/a").is_err());
+        assert!(parse_root_mapping("=/b").is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::models::{CollisionKind, FileMetadata, NameCollision};
+use crate::utils::PathNormalization;
+
+/// Finds the names that collide within a directory under Unicode normalization or case folding,
+/// i.e. the files and directories that would overwrite each other when copied to a filesystem
+/// that normalizes or ignores case. Directories are the parents of the files, since snapshots
+/// don't record empty directories.
+pub fn find_collisions(files: &[FileMetadata]) -> Vec<NameCollision> {
+    let mut entries = BTreeSet::new();
+    for file in files {
+        let mut path = file.path.as_path();
+        while path.file_name().is_some() && entries.insert(path) {
+            path = path.parent().unwrap_or(Path::new(""));
+        }
+    }
+
+    let mut groups: BTreeMap<(&Path, PathBuf), Vec<&Path>> = BTreeMap::new();
+    for path in entries {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            continue;
+        };
+        let key = PathNormalization::Casefold
+            .key(Path::new(name))
+            .into_owned();
+        groups.entry((parent, key)).or_default().push(path);
+    }
+
+    groups
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .map(|paths| {
+            let nfc = PathNormalization::Nfc.key(paths[0]);
+            let kind = if paths
+                .iter()
+                .all(|path| PathNormalization::Nfc.key(path) == nfc)
+            {
+                CollisionKind::Normalization
+            } else {
+                CollisionKind::Case
+            };
+            NameCollision {
+                kind,
+                paths: paths.into_iter().map(Path::to_path_buf).collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str) -> FileMetadata {
+        FileMetadata {
+            path: PathBuf::from(path),
+            bytes: 0,
+            modified_at: None,
+            created_at: None,
+            accessed_at: None,
+            content_hash: None,
+            hash_sampled: false,
+            disk_usage: None,
+        }
+    }
+
+    #[test]
+    fn test_find_collisions() {
+        let files = [
+            file("README.md"),
+            file("Readme.md"),
+            file("Cafe\u{301}.txt"),
+            file("Caf\u{e9}.txt"),
+            file("docs/a.txt"),
+            file("Docs/b.txt"),
+            file("src/a.txt"),
+            file("lib/A.txt"),
+        ];
+        let collisions = find_collisions(&files);
+        let found: Vec<(CollisionKind, Vec<&str>)> = collisions
+            .iter()
+            .map(|collision| {
+                let paths = collision
+                    .paths
+                    .iter()
+                    .map(|p| p.to_str().unwrap())
+                    .collect();
+                (collision.kind, paths)
+            })
+            .collect();
+
+        assert_eq!(found.len(), 3);
+        assert!(found.contains(&(
+            CollisionKind::Normalization,
+            vec!["Cafe\u{301}.txt", "Caf\u{e9}.txt"]
+        )));
+        assert!(found.contains(&(CollisionKind::Case, vec!["Docs", "docs"])));
+        assert!(found.contains(&(CollisionKind::Case, vec!["README.md", "Readme.md"])));
+    }
+}
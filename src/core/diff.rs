@@ -1,41 +1,53 @@
-use std::collections::{HashMap, HashSet};
-
-use crate::models::{Diff, FileMetadata};
-
+use std::path::PathBuf;
+
+use crate::models::{Change, Diff, FileMetadata};
+use crate::utils::PathNormalization;
+
+/// Computes the changes from `files1` to `files2`.
+///
+/// Content hashes are only compared if `compare_hashes` is set, i.e. if both sides were hashed
+/// with the same algorithm. Otherwise, changes in size or modification time mark files as modified.
+/// Files that could not be read on either side are reported as unreadable, unless their metadata
+/// shows they were modified. Paths are matched under `normalization`; files present on both sides
+/// are reported with their path in `files2`.
 pub fn diff_snapshots(
     files1: &[FileMetadata],
     files2: &[FileMetadata],
+    compare_hashes: bool,
+    normalization: PathNormalization,
 ) -> Result<Diff, Box<dyn std::error::Error>> {
-    let files1_map: HashMap<String, Option<String>> = files1
+    let files1_map = normalization.index(files1, |f| &f.path);
+    let files2_map = normalization.index(files2, |f| &f.path);
+
+    let added: Vec<PathBuf> = files2_map
         .iter()
-        .map(|f| (f.path.to_string_lossy().to_string(), f.content_hash.clone()))
+        .filter(|(key, _)| !files1_map.contains_key(*key))
+        .map(|(_, f)| f.path.clone())
         .collect();
 
-    let files2_map: HashMap<String, Option<String>> = files2
+    let removed: Vec<PathBuf> = files1_map
         .iter()
-        .map(|f| (f.path.to_string_lossy().to_string(), f.content_hash.clone()))
+        .filter(|(key, _)| !files2_map.contains_key(*key))
+        .map(|(_, f)| f.path.clone())
         .collect();
 
-    let files1_paths: HashSet<String> = files1_map.keys().cloned().collect();
-    let files2_paths: HashSet<String> = files2_map.keys().cloned().collect();
-
-    let added: Vec<String> = files2_paths.difference(&files1_paths).cloned().collect();
-
-    let removed: Vec<String> = files1_paths.difference(&files2_paths).cloned().collect();
-
-    let modified: Vec<String> = files1_paths
-        .intersection(&files2_paths)
-        .filter(|&path| {
-            let hash1 = files1_map.get(path).unwrap();
-            let hash2 = files2_map.get(path).unwrap();
-            hash1 != hash2
-        })
-        .cloned()
-        .collect();
+    let mut modified = Vec::new();
+    let mut unreadable = Vec::new();
+    for (key, file2) in &files2_map {
+        let Some(file1) = files1_map.get(key) else {
+            continue;
+        };
+        match file2.compare(file1, compare_hashes) {
+            Change::Unchanged => {}
+            Change::Modified => modified.push(file2.path.clone()),
+            Change::Unknown => unreadable.push(file2.path.clone()),
+        }
+    }
 
     Ok(Diff {
         added,
         removed,
         modified,
+        unreadable,
     })
 }
@@ -4,17 +4,19 @@ use std::time::SystemTime;
 use gix::bstr::ByteSlice;
 
 use crate::utils::hashing;
+use crate::utils::progress::Progress;
 use crate::{database, models, utils}; // Added utils back for get_chronicle_db_path
 
+/// Imports every commit reachable from `HEAD` as a snapshot. Returns the number of imported commits.
 pub fn sync_history(
     path: &Path,
     db_path_override: Option<&PathBuf>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<usize, Box<dyn std::error::Error>> {
     let root = std::fs::canonicalize(path)?;
     let repo = gix::open(&root)?;
     let head = repo.head_commit()?;
 
-    println!(
+    log::info!(
         "Starting Git history synchronization from: {}",
         root.display()
     );
@@ -22,16 +24,27 @@ pub fn sync_history(
     let db_path = utils::get_chronicle_db_path(db_path_override)?;
     let conn = database::open(&db_path)?;
 
-    // Iterate through all commits
-    let rev_walk = head.ancestors().all()?;
-    for commit_id_result in rev_walk {
-        let commit_id = commit_id_result?.id(); // Get the actual commit ID
+    let root_id = database::ensure_root(&conn, &root)?;
+
+    // Collect all commit ids upfront, so that progress can be reported as N/M
+    let commit_ids = head
+        .ancestors()
+        .all()?
+        .map(|info| info.map(|info| info.id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut progress = Progress::new("git", "commits").with_total_items(commit_ids.len() as u64);
+
+    // Iterate through all commits, oldest first: snapshots with the same timestamp are ordered
+    // by when they were recorded, so commits made within the same second keep their order
+    let mut imported = 0;
+    for commit_id in commit_ids.into_iter().rev() {
+        progress.inc(1, 0);
         let commit = repo.find_object(commit_id)?.try_into_commit()?;
         let tree = commit.tree()?;
 
         // Idempotency check
-        if database::snapshot_exists(&conn, &root.to_string_lossy(), &commit_id.to_string())? {
-            println!("Skipping already synchronized commit: {commit_id}");
+        if database::snapshot_exists(&conn, root_id, &commit_id.to_string())? {
+            log::debug!("Skipping already synchronized commit: {commit_id}");
             continue;
         }
 
@@ -64,6 +77,8 @@ pub fn sync_history(
                 created_at: None,
                 accessed_at: None,
                 content_hash: Some(content_hash),
+                hash_sampled: false,
+                disk_usage: None,
             });
         }
         files.sort_by(|a, b| a.path.cmp(&b.path));
@@ -72,14 +87,75 @@ pub fn sync_history(
             root: root.to_path_buf(),
             timestamp,
             git_commit_hash: Some(commit.id().to_string()),
+            hash_algorithm: hashing::HashAlgorithm::Blake3,
+            hash_mode: hashing::HashMode::Full,
+            // Git history is read from the object database, not from the files
+            atime_preserved: true,
+            scan_stats: None,
             files,
         };
 
-        database::store_snapshot(snapshot, db_path_override)?;
+        let (diff, snapshot_id) =
+            database::store_snapshot(snapshot, db_path_override, utils::PathNormalization::Exact)?;
+        if snapshot_id.is_some() {
+            imported += 1;
+        }
+
+        log::debug!(
+            "Processed commit: {commit_id} (+{} -{} *{})",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.modified.len()
+        );
+    }
 
-        println!("Processed commit: {commit_id}");
+    progress.finish();
+    log::info!("Git history synchronization completed, {imported} commits imported.");
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_DATE", "1700000000 +0000")
+            .env("GIT_COMMITTER_DATE", "1700000000 +0000")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
     }
 
-    println!("Git history synchronization completed.");
-    Ok(())
+    #[test]
+    fn test_commits_in_the_same_second() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        let db_path = dir.path().join("chronicle.db");
+
+        git(&repo, &["init", "--quiet"]);
+        std::fs::write(repo.join("a.txt"), "first").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "--quiet", "-m", "first"]);
+        std::fs::write(repo.join("a.txt"), "second").unwrap();
+        git(&repo, &["commit", "--quiet", "-am", "second"]);
+        let head = git(&repo, &["rev-parse", "HEAD"]);
+
+        assert_eq!(sync_history(&repo, Some(&db_path)).unwrap(), 2);
+
+        let conn = database::open(&db_path).unwrap();
+        let root = std::fs::canonicalize(&repo).unwrap();
+        let root_id = database::find_root_id(&conn, &root).unwrap().unwrap();
+        let latest = database::get_latest_snapshot_id(&conn, root_id)
+            .unwrap()
+            .unwrap();
+        let snapshot = database::get_snapshot(&conn, latest).unwrap().unwrap();
+        assert_eq!(snapshot.git_commit_hash, Some(head));
+    }
 }
@@ -1,4 +1,10 @@
+pub mod bundle;
+pub mod collisions;
 pub mod diff;
 pub mod git_sync;
+pub mod query;
+pub mod root;
 pub mod scan;
 pub mod snapshot;
+pub mod tree;
+pub mod verify;
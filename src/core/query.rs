@@ -0,0 +1,450 @@
+//! A small, typed filter language over the files of a snapshot.
+//!
+//! ```text
+//! ext = "mp4" and bytes > 1GiB and modified < 2024-01-01
+//! (name ~ "*.jpg" or name ~ "*.png") and not path ~ "cache/*"
+//! ```
+//!
+//! A query is a boolean combination (`and`, `or`, `not`, parentheses) of comparisons between a
+//! field and a literal. Every field has a type, and literals must match it:
+//!
+//! | Field                              | Type   | Literals                          |
+//! |------------------------------------|--------|-----------------------------------|
+//! | `path`, `name`, `ext`, `hash`      | text   | `"quoted"`                        |
+//! | `bytes` (alias `size`)             | size   | `1024`, `10KB`, `1.5GiB`          |
+//! | `modified`, `created`, `accessed`  | date   | `2024-01-01`, `2024-01-01T12:30`  |
+//!
+//! Comparisons use `=`, `!=`, `<`, `<=`, `>` and `>=`; text fields also support `~`, a glob match.
+//! Dates are in local time. Queries compile to an SQL condition over the `files` table.
+
+use rusqlite::types::Value;
+
+use crate::utils;
+
+/// An error in a query, with the position (in characters) where it was detected
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid query at position {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A query compiled to an SQL condition over the `files` table (aliased `f`), with its
+/// parameters bound to anonymous `?` placeholders in order
+#[derive(Debug)]
+pub struct Filter {
+    pub sql: String,
+    pub params: Vec<Value>,
+}
+
+/// Parses and type-checks a query, and compiles it to SQL
+pub fn compile(query: &str) -> Result<Filter, ParseError> {
+    let tokens = tokenize(query)?;
+    let mut parser = Parser {
+        tokens,
+        index: 0,
+        end: query.chars().count(),
+        params: Vec::new(),
+    };
+    let sql = parser.parse_or()?;
+    if let Some((token, position)) = parser.tokens.get(parser.index) {
+        return Err(ParseError {
+            message: format!("unexpected {token}"),
+            position: *position,
+        });
+    }
+    Ok(Filter {
+        sql,
+        params: parser.params,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Text(String),
+    /// A number, possibly followed by a size unit (e.g. `1.5GiB`)
+    Number(String),
+    /// A date, possibly with a time (e.g. `2024-01-01T12:30`)
+    Date(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "'{ident}'"),
+            Token::Text(text) => write!(f, "\"{text}\""),
+            Token::Number(number) | Token::Date(number) => write!(f, "'{number}'"),
+            Token::Op(op) => write!(f, "'{op}'"),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+        }
+    }
+}
+
+fn tokenize(query: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let take_while = |i: &mut usize, predicate: &dyn Fn(char) -> bool| {
+            let from = *i;
+            while *i < chars.len() && predicate(chars[*i]) {
+                *i += 1;
+            }
+            chars[from..*i].iter().collect::<String>()
+        };
+
+        let token = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                Token::LParen
+            }
+            ')' => {
+                i += 1;
+                Token::RParen
+            }
+            '"' => {
+                i += 1;
+                let mut text = String::new();
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(ParseError {
+                                message: "unterminated string".to_string(),
+                                position: start,
+                            });
+                        }
+                        Some('"') => break,
+                        Some('\\') if i + 1 < chars.len() => {
+                            text.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&c) => {
+                            text.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1;
+                Token::Text(text)
+            }
+            '=' | '~' => {
+                i += 1;
+                Token::Op(if c == '=' { "=" } else { "~" })
+            }
+            '!' | '<' | '>' => {
+                let with_eq = chars.get(i + 1) == Some(&'=');
+                i += if with_eq { 2 } else { 1 };
+                Token::Op(match (c, with_eq) {
+                    ('!', true) => "!=",
+                    ('<', true) => "<=",
+                    ('<', false) => "<",
+                    ('>', true) => ">=",
+                    ('>', false) => ">",
+                    _ => {
+                        return Err(ParseError {
+                            message: "expected '!='".to_string(),
+                            position: start,
+                        });
+                    }
+                })
+            }
+            _ if c.is_ascii_digit() => {
+                let digits = take_while(&mut i, &|c| c.is_ascii_digit() || c == '.');
+                if digits.len() == 4 && chars.get(i) == Some(&'-') {
+                    let rest = take_while(&mut i, &|c| c.is_ascii_digit() || "-:T".contains(c));
+                    Token::Date(digits + &rest)
+                } else {
+                    let unit = take_while(&mut i, &|c| c.is_ascii_alphabetic());
+                    Token::Number(digits + &unit)
+                }
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                Token::Ident(take_while(&mut i, &|c| c.is_alphanumeric() || c == '_'))
+            }
+            _ => {
+                return Err(ParseError {
+                    message: format!("unexpected character '{c}'"),
+                    position: start,
+                });
+            }
+        };
+        tokens.push((token, start));
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldType {
+    Text,
+    Size,
+    Date,
+}
+
+/// The path as text. Paths are stored as raw bytes, which never compare equal to text
+const PATH_SQL: &str = "CAST(f.path AS TEXT)";
+
+/// The file name, i.e. the path after the last `/`
+const NAME_SQL: &str = "substr(CAST(f.path AS TEXT), length(rtrim(CAST(f.path AS TEXT), replace(CAST(f.path AS TEXT), '/', ''))) + 1)";
+
+/// Resolves a field name to its type and SQL expression
+fn field(name: &str) -> Option<(FieldType, String)> {
+    let field = match name.to_lowercase().as_str() {
+        "path" => (FieldType::Text, PATH_SQL.to_string()),
+        "name" => (FieldType::Text, NAME_SQL.to_string()),
+        // The lowercase text after the last `.` of the name, empty without one
+        "ext" => (
+            FieldType::Text,
+            format!(
+                "lower(CASE WHEN instr({NAME_SQL}, '.') > 0 THEN substr({NAME_SQL}, length(rtrim({NAME_SQL}, replace({NAME_SQL}, '.', ''))) + 1) ELSE '' END)"
+            ),
+        ),
+        "hash" => (FieldType::Text, "f.content_hash".to_string()),
+        "bytes" | "size" => (FieldType::Size, "f.bytes".to_string()),
+        "modified" => (FieldType::Date, "f.modified_at".to_string()),
+        "created" => (FieldType::Date, "f.created_at".to_string()),
+        "accessed" => (FieldType::Date, "f.accessed_at".to_string()),
+        _ => return None,
+    };
+    Some(field)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    /// Position of the end of the query, for errors at the end of input
+    end: usize,
+    params: Vec<Value>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map(|(_, position)| *position)
+            .unwrap_or(self.end)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<(Token, usize), ParseError> {
+        let token = self.tokens.get(self.index).cloned().ok_or(ParseError {
+            message: format!("expected {expected}, found end of query"),
+            position: self.end,
+        })?;
+        self.index += 1;
+        Ok(token)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<String, ParseError> {
+        let mut sql = self.parse_and()?;
+        while self.is_keyword("or") {
+            self.index += 1;
+            sql = format!("{sql} OR {}", self.parse_and()?);
+        }
+        Ok(sql)
+    }
+
+    fn parse_and(&mut self) -> Result<String, ParseError> {
+        let mut sql = self.parse_unary()?;
+        while self.is_keyword("and") {
+            self.index += 1;
+            sql = format!("{sql} AND {}", self.parse_unary()?);
+        }
+        Ok(sql)
+    }
+
+    fn parse_unary(&mut self) -> Result<String, ParseError> {
+        if self.is_keyword("not") {
+            self.index += 1;
+            return Ok(format!("NOT {}", self.parse_unary()?));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.index += 1;
+            let sql = self.parse_or()?;
+            match self.next("')'")? {
+                (Token::RParen, _) => return Ok(format!("({sql})")),
+                (token, position) => {
+                    return Err(ParseError {
+                        message: format!("expected ')', found {token}"),
+                        position,
+                    });
+                }
+            }
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<String, ParseError> {
+        let (name, position) = match self.next("a field")? {
+            (Token::Ident(name), position) => (name, position),
+            (token, position) => {
+                return Err(ParseError {
+                    message: format!("expected a field, found {token}"),
+                    position,
+                });
+            }
+        };
+        let (field_type, column) = field(&name).ok_or(ParseError {
+            message: format!("unknown field '{name}'"),
+            position,
+        })?;
+
+        let op = match self.next("an operator")? {
+            (Token::Op(op), _) => op,
+            (token, position) => {
+                return Err(ParseError {
+                    message: format!("expected an operator, found {token}"),
+                    position,
+                });
+            }
+        };
+        if op == "~" && field_type != FieldType::Text {
+            return Err(ParseError {
+                message: format!("'~' only applies to text fields, not '{name}'"),
+                position,
+            });
+        }
+
+        let literal_position = self.position();
+        let (literal, _) = self.next("a value")?;
+        let mismatch = |expected: &str| ParseError {
+            message: format!("'{name}' expects {expected}, found {literal}"),
+            position: literal_position,
+        };
+        let value = match (field_type, &literal) {
+            // Extensions are compared in lowercase on both sides, like SQLite's lower()
+            (FieldType::Text, Token::Text(text)) if name.eq_ignore_ascii_case("ext") => {
+                Value::Text(text.to_ascii_lowercase())
+            }
+            (FieldType::Text, Token::Text(text)) => Value::Text(text.clone()),
+            (FieldType::Size, Token::Number(number)) => {
+                let bytes = utils::parse_size(number).map_err(|message| ParseError {
+                    message,
+                    position: literal_position,
+                })?;
+                Value::Integer(bytes as i64)
+            }
+            (FieldType::Date, Token::Date(text) | Token::Text(text)) => {
+                Value::Integer(utils::parse_timestamp(text).ok_or(ParseError {
+                    message: format!("invalid date '{text}'"),
+                    position: literal_position,
+                })?)
+            }
+            (FieldType::Text, _) => return Err(mismatch("a quoted string")),
+            (FieldType::Size, _) => return Err(mismatch("a size (e.g. 10MiB)")),
+            (FieldType::Date, _) => return Err(mismatch("a date (e.g. 2024-01-01)")),
+        };
+        self.params.push(value);
+
+        let sql_op = match op {
+            "~" => "GLOB",
+            op => op,
+        };
+        Ok(format!("{column} {sql_op} ?"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile() {
+        let filter = compile(r#"ext = "mp4" and bytes > 1GiB or not (path ~ "a/*")"#).unwrap();
+        assert!(
+            filter
+                .sql
+                .ends_with("= ? AND f.bytes > ? OR NOT (CAST(f.path AS TEXT) GLOB ?)")
+        );
+        assert_eq!(
+            filter.params,
+            vec![
+                Value::Text("mp4".to_string()),
+                Value::Integer(1024 * 1024 * 1024),
+                Value::Text("a/*".to_string()),
+            ]
+        );
+
+        let filter = compile("modified < 2024-01-01").unwrap();
+        assert_eq!(filter.sql, "f.modified_at < ?");
+        assert_eq!(
+            filter.params,
+            vec![Value::Integer(
+                utils::parse_timestamp("2024-01-01").unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let error = compile("color = \"red\"").unwrap_err();
+        assert_eq!(error.position, 0);
+        assert!(error.message.contains("unknown field"));
+
+        let error = compile("bytes > \"big\"").unwrap_err();
+        assert_eq!(error.position, 8);
+
+        assert!(compile("bytes ~ 10").is_err());
+        assert!(compile("modified < 2024-13-01").is_err());
+        assert!(compile("name = \"a\" and").is_err());
+        assert!(compile("(name = \"a\"").is_err());
+        assert!(compile("name = \"a\" name").is_err());
+    }
+
+    #[test]
+    fn test_name_and_ext() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let eval = |query: &str, path: &str| -> bool {
+            let filter = compile(query).unwrap();
+            // Placeholders are numbered in textual order, the filter's come first
+            let mut params = filter.params;
+            params.push(Value::Text(path.to_string()));
+            conn.query_row(
+                &format!("SELECT {} FROM (SELECT ? AS path) f", filter.sql),
+                rusqlite::params_from_iter(params),
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        assert!(eval(r#"name = "clip.MP4""#, "videos/2024/clip.MP4"));
+        assert!(eval(r#"ext = "mp4""#, "videos/2024/clip.MP4"));
+        assert!(eval(r#"ext = "MP4""#, "videos/2024/clip.MP4"));
+        assert!(eval(r#"ext = "Mp4""#, "videos/2024/clip.mp4"));
+        assert!(!eval(r#"ext != "MP4""#, "videos/2024/clip.mp4"));
+        assert!(eval(r#"ext ~ "MP*""#, "videos/2024/clip.mp4"));
+        assert!(eval(r#"ext = "gz""#, "archive.tar.gz"));
+        assert!(eval(r#"ext = """#, "dir.d/Makefile"));
+        assert!(eval(r#"name ~ "*.tar.*""#, "a/archive.tar.gz"));
+    }
+}
@@ -0,0 +1,208 @@
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+
+use crate::database;
+use crate::utils::display_path;
+
+/// Name of the marker file that carries a root's persistent identity across moves and remounts
+pub const MARKER_FILE: &str = ".chronicle-root";
+
+/// Reads the persistent identity recorded in the root's marker file, if any
+pub fn read_marker(root: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(root.join(MARKER_FILE)).ok()?;
+    let uuid = content.trim();
+    (!uuid.is_empty()).then(|| uuid.to_string())
+}
+
+/// Records the persistent identity of the root in its marker file
+pub fn write_marker(root: &Path, uuid: &str) -> std::io::Result<()> {
+    std::fs::write(root.join(MARKER_FILE), format!("{uuid}\n"))
+}
+
+/// Resolves a command-line path argument to a canonical path.
+///
+/// The argument may also be the friendly name of a tracked root (see `chronicle track`), which is
+/// only looked up when no such path exists. If the argument is both an existing path and the name
+/// of another root, it is ambiguous and rejected.
+pub fn resolve_path(conn: &Connection, arg: &Path) -> std::io::Result<PathBuf> {
+    let directory = std::fs::canonicalize(arg);
+    let named =
+        database::find_root_by_name(conn, &arg.to_string_lossy()).map_err(std::io::Error::other)?;
+    let Some((_, named)) = named else {
+        return directory;
+    };
+    // The root may live on a drive that is not mounted at the moment
+    let named = std::fs::canonicalize(&named).unwrap_or(named);
+    match directory {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(named),
+        Ok(directory) if directory != named => Err(std::io::Error::other(format!(
+            "'{}' is ambiguous: it is both the directory {} and the name of the root {}. Pass the directory as ./{0} or the root by its path.",
+            display_path(arg),
+            display_path(&directory),
+            display_path(&named)
+        ))),
+        directory => directory,
+    }
+}
+
+/// Resolves a directory to the id of its tracked root, without writing to the database.
+///
+/// The canonical path is looked up first. If the directory is not tracked under that path but
+/// carries the marker of a known root whose old location is gone (or no longer carries the same
+/// marker), the root has been moved or remounted: its id is returned, and a hint to record the
+/// new location with `chronicle root move` is logged.
+pub fn resolve(conn: &Connection, root: &Path) -> rusqlite::Result<Option<i64>> {
+    if let Some(id) = database::find_root_id(conn, root)? {
+        return Ok(Some(id));
+    }
+
+    let Some((id, old_path)) = find_moved(conn, root)? else {
+        return Ok(None);
+    };
+    log::warn!(
+        "{} is the root previously at {}. Run `chronicle root move {} {}` or take a snapshot to record its new location.",
+        display_path(root),
+        display_path(&old_path),
+        display_path(&old_path),
+        display_path(root)
+    );
+    Ok(Some(id))
+}
+
+/// Like [`resolve`], but a moved or remounted root gets its path updated, so that its history
+/// continues at the new location
+pub fn resolve_and_update(conn: &Connection, root: &Path) -> rusqlite::Result<Option<i64>> {
+    if let Some(id) = database::find_root_id(conn, root)? {
+        return Ok(Some(id));
+    }
+
+    let Some((id, old_path)) = find_moved(conn, root)? else {
+        return Ok(None);
+    };
+    log::info!(
+        "Root moved from {} to {}",
+        display_path(&old_path),
+        display_path(root)
+    );
+    database::set_root_path(conn, id, root)?;
+    Ok(Some(id))
+}
+
+/// Finds the known root whose marker the directory carries, if its old location is gone. Returns
+/// its id and old path.
+fn find_moved(conn: &Connection, root: &Path) -> rusqlite::Result<Option<(i64, PathBuf)>> {
+    let Some(uuid) = read_marker(root) else {
+        return Ok(None);
+    };
+    let Some((id, old_path)) = database::find_root_by_uuid(conn, &uuid)? else {
+        return Ok(None);
+    };
+
+    // A copy of a marked directory is not a move, the original is still in place
+    if read_marker(&old_path).as_deref() == Some(uuid.as_str()) {
+        log::warn!(
+            "{} carries the identity of {}, which still exists. Treating it as a new root.",
+            display_path(root),
+            display_path(&old_path)
+        );
+        return Ok(None);
+    }
+    Ok(Some((id, old_path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{ensure_root, get_root_uuid, initialize_schema};
+
+    fn create_in_memory_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_resolve_follows_marker_after_move() {
+        let conn = create_in_memory_db();
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old");
+        let new = dir.path().join("new");
+        std::fs::create_dir(&old).unwrap();
+
+        let id = ensure_root(&conn, &old).unwrap();
+        write_marker(&old, &get_root_uuid(&conn, id).unwrap()).unwrap();
+        std::fs::rename(&old, &new).unwrap();
+
+        // Read-only commands recognize the root without recording the move
+        assert_eq!(resolve(&conn, &new).unwrap(), Some(id));
+        assert_eq!(database::find_root_id(&conn, &new).unwrap(), None);
+        assert_eq!(database::find_root_id(&conn, &old).unwrap(), Some(id));
+
+        assert_eq!(resolve_and_update(&conn, &new).unwrap(), Some(id));
+        assert_eq!(database::find_root_id(&conn, &new).unwrap(), Some(id));
+    }
+
+    #[test]
+    fn test_resolve_path_accepts_names() {
+        let conn = create_in_memory_db();
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+
+        let id = ensure_root(&conn, &root).unwrap();
+        database::track_root(&conn, id, Some("scratch")).unwrap();
+
+        assert_eq!(resolve_path(&conn, Path::new("scratch")).unwrap(), root);
+        assert_eq!(resolve_path(&conn, &root).unwrap(), root);
+        assert!(resolve_path(&conn, Path::new("no-such-root-or-dir")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_path_rejects_ambiguous_names() {
+        let conn = create_in_memory_db();
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        // A directory named like the root, relative to the working directory
+        let local = tempfile::Builder::new()
+            .prefix(".chronicle-test-")
+            .tempdir_in(".")
+            .unwrap();
+        let name = local
+            .path()
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let id = ensure_root(&conn, &root).unwrap();
+        database::track_root(&conn, id, Some(&name)).unwrap();
+        let err = resolve_path(&conn, Path::new(&name)).unwrap_err();
+        assert!(err.to_string().contains("ambiguous"));
+        let local_path = std::fs::canonicalize(local.path()).unwrap();
+        let dot_name = Path::new(".").join(&name);
+        assert_eq!(resolve_path(&conn, &dot_name).unwrap(), local_path);
+
+        // Once the directory is gone, the name designates the root again
+        drop(local);
+        assert_eq!(resolve_path(&conn, Path::new(&name)).unwrap(), root);
+    }
+
+    #[test]
+    fn test_resolve_ignores_copies() {
+        let conn = create_in_memory_db();
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original");
+        let copy = dir.path().join("copy");
+        std::fs::create_dir(&original).unwrap();
+        std::fs::create_dir(&copy).unwrap();
+
+        let id = ensure_root(&conn, &original).unwrap();
+        let uuid = get_root_uuid(&conn, id).unwrap();
+        write_marker(&original, &uuid).unwrap();
+        write_marker(&copy, &uuid).unwrap();
+
+        assert_eq!(resolve(&conn, &copy).unwrap(), None);
+        assert_eq!(resolve_and_update(&conn, &copy).unwrap(), None);
+        assert_eq!(resolve(&conn, &original).unwrap(), Some(id));
+    }
+}
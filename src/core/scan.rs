@@ -1,53 +1,417 @@
+use clap::ValueEnum;
 use ignore::WalkBuilder;
+use rusqlite::Connection;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use crate::{models, utils};
+use crate::core::root;
+use crate::models::{ScanError, ScanStats};
+use crate::utils::PathNormalization;
+use crate::utils::hashing::{self, HashAlgorithm, HashMode, Throttle};
+use crate::utils::priority::{self, IoClass};
+use crate::utils::progress::Progress;
+use crate::{database, models, utils};
 
-pub fn scan(root: &Path) -> Result<Vec<models::FileMetadata>, Box<dyn std::error::Error>> {
-    let mut files = Vec::new();
+/// Settings that control how a directory is scanned
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// The algorithm used to hash file contents
+    pub hash_algorithm: HashAlgorithm,
+    /// Whether file contents are hashed at all
+    pub hash_mode: HashMode,
+    /// Maximum rate at which file contents are read, in bytes per second
+    pub max_read_rate: Option<u64>,
+    /// Niceness the scanning thread runs at
+    pub nice: Option<i32>,
+    /// I/O scheduling class the scanning thread is lowered to
+    pub ionice: Option<IoClass>,
+    /// What to do with paths that can't be read
+    pub on_error: ErrorPolicy,
+    /// How paths are matched against the previous snapshot
+    pub normalization: PathNormalization,
+}
+
+/// What a scan does with paths that can't be read
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Leave files that can't be hashed out of the scan
+    Skip,
+    /// Abort the scan
+    Fail,
+    /// Keep unreadable files without a content hash (default)
+    #[default]
+    Record,
+}
+
+impl std::fmt::Display for ErrorPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorPolicy::Skip => write!(f, "skip"),
+            ErrorPolicy::Fail => write!(f, "fail"),
+            ErrorPolicy::Record => write!(f, "record"),
+        }
+    }
+}
+
+impl ScanOptions {
+    /// Settings that hash files like the given snapshot, so that their hashes can be compared
+    pub fn like_snapshot(conn: &Connection, snapshot_id: i64) -> rusqlite::Result<ScanOptions> {
+        let (hash_algorithm, hash_mode) =
+            database::get_snapshot_hashing(conn, snapshot_id)?.unwrap_or_default();
+        Ok(ScanOptions {
+            hash_algorithm,
+            hash_mode,
+            ..Default::default()
+        })
+    }
+
+    /// Sets the priority of the calling thread as configured
+    fn lower_priority(&self) {
+        if let Some(nice) = self.nice
+            && let Err(e) = priority::set_nice(nice)
+        {
+            log::warn!("Could not set the niceness to {nice}: {e}");
+        }
+        if let Some(class) = self.ionice
+            && let Err(e) = priority::set_io_class(class)
+        {
+            log::warn!("Could not set the I/O scheduling class to {class}: {e}");
+        }
+    }
+}
+
+/// The outcome of scanning a directory
+#[derive(Debug)]
+pub struct Scan {
+    /// The files found, sorted by path
+    pub files: Vec<models::FileMetadata>,
+    /// Whether hashing left the access times of all files untouched. If not, the access times
+    /// recorded by the next scan reflect this scan rather than actual use.
+    pub atime_preserved: bool,
+    /// Statistics about the scan, including the paths that could not be read
+    pub stats: ScanStats,
+}
+
+/// Walks a directory and collects the metadata of its files, hashing them as configured.
+///
+/// The metadata (including the access time) of every file is captured during the walk, before
+/// any file is read for hashing. Paths that can't be read are logged and recorded in the
+/// statistics. Unless the error policy is [`ErrorPolicy::Fail`], they don't fail the scan: files
+/// that can't be hashed are then left out ([`ErrorPolicy::Skip`]) or kept without a content hash.
+/// Files whose metadata can't be read are always left out. Diffs report the files left out as
+/// unreadable, not removed, as their paths are among the errors.
+///
+/// If a niceness or I/O scheduling class is configured, the scan runs on a thread of its own at
+/// that priority, so that it doesn't carry over to later scans (e.g. of the other roots of
+/// `snapshot --all`): a lowered priority can't be raised back without privileges.
+pub fn scan(root: &Path, options: &ScanOptions) -> Result<Scan, Box<dyn std::error::Error>> {
+    if options.nice.is_none() && options.ionice.is_none() {
+        return scan_files(root, options);
+    }
+    std::thread::scope(|scope| {
+        let scan = scope.spawn(|| {
+            options.lower_priority();
+            scan_files(root, options).map_err(|e| e.to_string())
+        });
+        match scan.join() {
+            Ok(result) => result.map_err(Into::into),
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    })
+}
+
+/// Scans the directory on the calling thread, see [`scan`]
+fn scan_files(root: &Path, options: &ScanOptions) -> Result<Scan, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let mut entries = Vec::new();
+    let mut stats = ScanStats::default();
+    let mut record_error = |path: &Path, error: String| {
+        if options.on_error == ErrorPolicy::Fail {
+            return Err(format!(
+                "Could not read {}: {error}",
+                utils::display_path(path)
+            ));
+        }
+        log::warn!("Could not read {}: {error}", utils::display_path(path));
+        stats.errors.push(ScanError {
+            path: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+            error,
+        });
+        Ok(())
+    };
 
     // Create a walker to scan the directory
-    let walker = WalkBuilder::new(root).build();
+    let ignored = Arc::new(AtomicU64::new(0));
+    let walker = build_walker(root, Arc::clone(&ignored));
+    let mut progress = Progress::new("walk", "files");
 
-    // Iterate over the entries in the directory
+    // Iterate over the entries in the directory. Hashing is deferred until the walk
+    // completes, so that the total number of bytes to hash is known upfront.
     for result in walker {
         let entry = match result {
             Ok(entry) => entry,
             Err(err) => {
-                eprintln!("Walk Error: {err}");
+                let path = error_path(&err).unwrap_or(root).to_path_buf();
+                record_error(&path, err.to_string())?;
                 continue;
             }
         };
 
         // Skip directories for now
-        if !entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+
+        // Skip the root's identity marker, it is chronicle's own bookkeeping
+        if entry.depth() == 1 && entry.file_name() == root::MARKER_FILE {
             continue;
         }
 
         // Get the metadata of the file
-        let metadata = entry.metadata()?;
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                record_error(entry.path(), err.to_string())?;
+                continue;
+            }
+        };
+        progress.inc(1, 0);
+        entries.push((entry.into_path(), metadata));
+    }
+    progress.finish();
+
+    stats.files_visited = entries.len() as u64;
+    stats.paths_ignored = ignored.load(Ordering::Relaxed);
+
+    // Without hashing, no file content is read
+    let hash_contents = options.hash_mode != HashMode::Metadata;
+    let total_bytes = entries
+        .iter()
+        .map(|(_, metadata)| options.hash_mode.bytes_read(metadata.len()))
+        .sum();
+    let mut progress = Progress::new(if hash_contents { "hash" } else { "stat" }, "files")
+        .with_total_items(entries.len() as u64);
+    if hash_contents {
+        progress = progress.with_total_bytes(total_bytes);
+    }
 
-        let full_path = entry.path();
+    let mut files = Vec::with_capacity(entries.len());
+    let mut atime_preserved = true;
+    let mut throttle = Throttle::new(options.max_read_rate);
+    for (full_path, metadata) in entries {
         let relative_path = full_path
             .strip_prefix(root)
-            .unwrap_or(full_path)
+            .unwrap_or(&full_path)
             .to_path_buf();
 
-        // Print the metadata
+        let sampled = options.hash_mode.samples(metadata.len());
+        let hash = if sampled {
+            Some(hashing::hash_file_sampled(
+                &full_path,
+                options.hash_algorithm,
+                &mut throttle,
+            ))
+        } else if hash_contents {
+            Some(hashing::hash_file(
+                &full_path,
+                options.hash_algorithm,
+                &mut throttle,
+            ))
+        } else {
+            None
+        };
+        let hash = match hash {
+            Some(Ok(hash)) => {
+                stats.bytes_hashed += options.hash_mode.bytes_read(metadata.len());
+                Some(hash)
+            }
+            Some(Err(err)) => {
+                record_error(&full_path, err.to_string())?;
+                if options.on_error == ErrorPolicy::Skip {
+                    progress.inc(1, options.hash_mode.bytes_read(metadata.len()));
+                    continue;
+                }
+                None
+            }
+            None => None,
+        };
+        if hash.as_ref().is_some_and(|hash| !hash.atime_preserved) {
+            log::debug!(
+                "Access time of {} not preserved",
+                utils::display_path(&full_path)
+            );
+            atime_preserved = false;
+        }
+
         let metadata = models::FileMetadata {
             path: relative_path,
             bytes: metadata.len(),
             modified_at: metadata.modified().ok(),
             created_at: metadata.created().ok(),
             accessed_at: metadata.accessed().ok(),
-            content_hash: utils::hashing::hash_file(&entry.path().to_path_buf()).ok(),
+            content_hash: hash.map(|hash| hash.hash),
+            hash_sampled: sampled,
+            disk_usage: models::DiskUsage::from_metadata(&metadata),
         };
 
+        progress.inc(1, options.hash_mode.bytes_read(metadata.bytes));
         files.push(metadata);
     }
+    progress.finish();
 
     // Sort files by path to ensure deterministic order
     files.sort_by(|a, b| a.path.cmp(&b.path));
 
-    Ok(files)
+    stats.duration = started.elapsed();
+    if stats.bytes_hashed > 0 {
+        log::info!(
+            "Hashed {} in {:.1}s ({}/s)",
+            utils::format_size_auto(stats.bytes_hashed),
+            stats.duration.as_secs_f64(),
+            utils::format_size_auto(stats.read_rate())
+        );
+    }
+    if !stats.is_complete() {
+        log::warn!("{} paths could not be read", stats.errors.len());
+    }
+    Ok(Scan {
+        files,
+        atime_preserved,
+        stats,
+    })
+}
+
+/// The path a walk error is about, if any
+fn error_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(err)
+        }
+        ignore::Error::Loop { child, .. } => Some(child),
+        _ => None,
+    }
+}
+
+/// Builds a walker over `root` that applies the usual ignore rules (hidden files, `.ignore`,
+/// `.gitignore`...) itself, so that the paths they exclude can be counted in `ignored` as they
+/// are skipped. An ignored directory counts once, as its content isn't listed.
+fn build_walker(root: &Path, ignored: Arc<AtomicU64>) -> ignore::Walk {
+    let mut builder = WalkBuilder::new(root);
+    // The builder was given a single root, so it has a single matcher
+    let matcher = Mutex::new(builder.build_matchers().remove(0));
+    let root = root.to_path_buf();
+    builder.standard_filters(false).filter_entry(move |entry| {
+        let path = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+        let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+        let (matched, err) = matcher.lock().unwrap().matched_with_errors(path, is_dir);
+        if let Some(err) = err {
+            log::warn!("Could not load ignore rules: {err}");
+        }
+        if matched.is_ignore() {
+            ignored.fetch_add(1, Ordering::Relaxed);
+        }
+        !matched.is_ignore()
+    });
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.txt"), b"abc").unwrap();
+        std::fs::write(root.join(".hidden"), b"").unwrap();
+        std::fs::write(root.join(".ignore"), b"skip/\n").unwrap();
+        std::fs::create_dir(root.join("skip")).unwrap();
+        std::fs::write(root.join("skip/x.txt"), b"x").unwrap();
+        std::fs::write(root.join("skip/y.txt"), b"y").unwrap();
+
+        let scan = scan(root, &ScanOptions::default()).unwrap();
+        assert_eq!(scan.files.len(), 1);
+        assert_eq!(scan.stats.files_visited, 1);
+        // .hidden, .ignore and skip/
+        assert_eq!(scan.stats.paths_ignored, 3);
+        assert_eq!(scan.stats.bytes_hashed, 3);
+        assert!(scan.stats.is_complete());
+    }
+
+    #[test]
+    fn test_scan_ignore_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("src/build")).unwrap();
+        std::fs::write(root.join(".gitignore"), b"*.log\n!keep.log\n").unwrap();
+        std::fs::write(root.join("src/.ignore"), b"build/\n").unwrap();
+        for name in [
+            "a.txt",
+            "a.log",
+            "keep.log",
+            "src/b.txt",
+            "src/b.log",
+            "src/build/c.txt",
+        ] {
+            std::fs::write(root.join(name), b"x").unwrap();
+        }
+
+        let scan = scan(root, &ScanOptions::default()).unwrap();
+        let paths: Vec<_> = scan.files.iter().map(|f| f.path.clone()).collect();
+        let mut expected: Vec<_> = WalkBuilder::new(root)
+            .build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+            .map(|entry| entry.path().strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        expected.sort();
+        assert_eq!(paths, expected);
+        assert_eq!(
+            paths,
+            ["a.txt", "keep.log", "src/b.txt"].map(std::path::PathBuf::from)
+        );
+        // .git, .gitignore, a.log, src/.ignore, src/b.log and src/build/
+        assert_eq!(scan.stats.paths_ignored, 6);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_scan_priority_does_not_carry_over() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), b"abc").unwrap();
+
+        // SAFETY: getpriority has no memory safety requirements
+        let niceness = || unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+        let before = niceness();
+        let options = ScanOptions {
+            nice: Some(19),
+            ..Default::default()
+        };
+        let scan = scan(dir.path(), &options).unwrap();
+        assert_eq!(scan.files.len(), 1);
+        assert_eq!(niceness(), before);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_disk_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.txt"), b"abc").unwrap();
+        std::fs::hard_link(root.join("a.txt"), root.join("b.txt")).unwrap();
+        std::fs::write(root.join("c.txt"), b"abc").unwrap();
+
+        let scan = scan(root, &ScanOptions::default()).unwrap();
+        let usage = |name: &str| {
+            let file = scan.files.iter().find(|f| f.path == Path::new(name));
+            file.unwrap().disk_usage.unwrap()
+        };
+        assert_eq!(usage("a.txt"), usage("b.txt"));
+        assert_ne!(usage("a.txt").inode, usage("c.txt").inode);
+    }
 }
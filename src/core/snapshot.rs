@@ -1,20 +1,35 @@
 use crate::core::git_sync;
-use crate::core::scan; // Added this
-use crate::{database, models};
-use std::path::{Path, PathBuf}; // Added this // Added this
+use crate::core::root;
+use crate::core::scan::{self, ScanOptions};
+use crate::{database, models, utils};
+use std::path::{Path, PathBuf};
+
+/// The result of snapshotting a single root
+pub enum SnapshotOutcome {
+    /// The directory was scanned. Holds the changes since the previous snapshot
+    /// and the id of the stored snapshot, which is `None` if nothing changed.
+    Scanned {
+        diff: models::Diff,
+        snapshot_id: Option<i64>,
+    },
+    /// The Git history was synchronized. Holds the number of imported commits.
+    Synced { imported: usize },
+}
 
 pub fn take_snapshot(
     path: &Path,
     db_path_override: Option<&PathBuf>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    options: &ScanOptions,
+) -> Result<SnapshotOutcome, Box<dyn std::error::Error>> {
     let root = std::fs::canonicalize(path)?;
 
     if is_git_repository(&root) {
-        println!("Git repository detected, synchronizing history up to HEAD...");
-        git_sync::sync_history(&root, db_path_override) // Changed to git_sync::sync_history
+        log::info!("Git repository detected, synchronizing history up to HEAD...");
+        let imported = git_sync::sync_history(&root, db_path_override)?;
+        Ok(SnapshotOutcome::Synced { imported })
     } else {
-        println!("Scanning directory: {}", root.display());
-        take_snapshot_from_fs(&root, db_path_override)
+        log::info!("Scanning directory: {}", root.display());
+        take_snapshot_from_fs(&root, db_path_override, options)
     }
 }
 
@@ -25,16 +40,27 @@ fn is_git_repository(path: &Path) -> bool {
 fn take_snapshot_from_fs(
     root: &Path,
     db_path_override: Option<&PathBuf>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let files = scan::scan(root)?;
+    options: &ScanOptions,
+) -> Result<SnapshotOutcome, Box<dyn std::error::Error>> {
+    // Follow the root if it was moved or remounted since the last snapshot
+    let db_path = utils::get_chronicle_db_path(db_path_override)?;
+    root::resolve_and_update(&database::open(&db_path)?, root)?;
+
+    let scan = scan::scan(root, options)?;
 
     // Create Snapshot
     let snapshot = models::Snapshot {
         root: root.to_path_buf(),
         timestamp: std::time::SystemTime::now(),
         git_commit_hash: None,
-        files,
+        hash_algorithm: options.hash_algorithm,
+        hash_mode: options.hash_mode,
+        atime_preserved: scan.atime_preserved,
+        scan_stats: Some(scan.stats),
+        files: scan.files,
     };
 
-    database::store_snapshot(snapshot, db_path_override)
+    let (diff, snapshot_id) =
+        database::store_snapshot(snapshot, db_path_override, options.normalization)?;
+    Ok(SnapshotOutcome::Scanned { diff, snapshot_id })
 }
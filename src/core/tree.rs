@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+use std::time::SystemTime;
+
+use crate::models::FileMetadata;
+use crate::utils::display_path;
+
+/// A directory reconstructed from the relative paths of the files of a snapshot
+#[derive(Debug, Default)]
+pub struct DirNode {
+    pub dirs: BTreeMap<String, DirNode>,
+    pub files: BTreeMap<String, FileMetadata>,
+}
+
+/// The names of the components of a relative path, as displayed
+fn names(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(display_path(Path::new(name)).into_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+impl DirNode {
+    /// Builds the directory tree of a snapshot. Directories only exist as parents of files,
+    /// since snapshots don't record empty directories.
+    pub fn build(files: &[FileMetadata]) -> DirNode {
+        let mut root = DirNode::default();
+        for file in files {
+            let mut names = names(&file.path);
+            let Some(name) = names.pop() else {
+                continue;
+            };
+            let mut dir = &mut root;
+            for parent in names {
+                dir = dir.dirs.entry(parent).or_default();
+            }
+            dir.files.insert(name, file.clone());
+        }
+        root
+    }
+
+    /// Finds the directory at the given relative path
+    pub fn find(&self, path: &Path) -> Option<&DirNode> {
+        names(path)
+            .iter()
+            .try_fold(self, |dir, name| dir.dirs.get(name))
+    }
+
+    /// Total size of the files in this directory and its subdirectories
+    pub fn total_bytes(&self) -> u64 {
+        self.files.values().map(|f| f.bytes).sum::<u64>()
+            + self.dirs.values().map(DirNode::total_bytes).sum::<u64>()
+    }
+
+    /// Number of files in this directory and its subdirectories
+    pub fn file_count(&self) -> usize {
+        self.files.len() + self.dirs.values().map(DirNode::file_count).sum::<usize>()
+    }
+
+    /// Latest modification time of the files in this directory and its subdirectories
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.files
+            .values()
+            .filter_map(|f| f.modified_at)
+            .chain(self.dirs.values().filter_map(DirNode::last_modified))
+            .max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn file(path: &str, bytes: u64, modified: u64) -> FileMetadata {
+        FileMetadata {
+            path: PathBuf::from(path),
+            bytes,
+            modified_at: Some(UNIX_EPOCH + Duration::from_secs(modified)),
+            created_at: None,
+            accessed_at: None,
+            content_hash: None,
+            hash_sampled: false,
+            disk_usage: None,
+        }
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let tree = DirNode::build(&[
+            file("README.md", 1, 1),
+            file("src/main.rs", 10, 5),
+            file("src/core/tree.rs", 100, 3),
+        ]);
+
+        assert_eq!(tree.files.keys().collect::<Vec<_>>(), vec!["README.md"]);
+        assert_eq!((tree.total_bytes(), tree.file_count()), (111, 3));
+
+        let src = tree.find(Path::new("src")).unwrap();
+        assert_eq!((src.total_bytes(), src.file_count()), (110, 2));
+        assert_eq!(
+            src.last_modified(),
+            Some(UNIX_EPOCH + Duration::from_secs(5))
+        );
+
+        assert!(tree.find(Path::new("src/core")).is_some());
+        assert!(tree.find(Path::new("./src/core/")).is_some());
+        assert!(tree.find(Path::new("docs")).is_none());
+    }
+}
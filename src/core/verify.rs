@@ -0,0 +1,142 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::models::{FileMetadata, IssueKind, Severity, VerifyIssue, VerifyReport, same_time};
+
+/// Compares the live files against the files stored in a snapshot, looking for bit rot.
+///
+/// A file whose content hash changed while its size and modification time stayed the same
+/// was not edited through the filesystem, so its content most likely decayed on the medium.
+/// Modification times are compared to the nanosecond, or to the second against times recorded
+/// without sub-second precision (see [`same_time`]).
+pub fn verify(live: &[FileMetadata], stored: &[FileMetadata]) -> VerifyReport {
+    let stored: HashMap<&Path, &FileMetadata> =
+        stored.iter().map(|f| (f.path.as_path(), f)).collect();
+    let mut report = VerifyReport::default();
+    let mut seen = HashSet::with_capacity(live.len());
+
+    for file in live {
+        let Some(expected) = stored.get(file.path.as_path()) else {
+            report.added += 1;
+            continue;
+        };
+        seen.insert(file.path.as_path());
+
+        let Some(actual_hash) = &file.content_hash else {
+            report.issues.push(issue(&file.path, IssueKind::Unreadable));
+            continue;
+        };
+        let Some(expected_hash) = &expected.content_hash else {
+            report.skipped += 1;
+            continue;
+        };
+        // A sampled hash can't be compared with a full one
+        if file.hash_sampled != expected.hash_sampled {
+            report.skipped += 1;
+            continue;
+        }
+
+        if actual_hash == expected_hash {
+            report.verified += 1;
+        } else if file.bytes == expected.bytes && same_time(file.modified_at, expected.modified_at)
+        {
+            report.issues.push(issue(&file.path, IssueKind::Corrupted));
+        } else {
+            report.modified += 1;
+        }
+    }
+
+    let mut missing: Vec<&Path> = stored
+        .keys()
+        .copied()
+        .filter(|path| !seen.contains(path))
+        .collect();
+    missing.sort();
+    for path in missing {
+        report.issues.push(issue(path, IssueKind::Missing));
+    }
+
+    // Most serious issues first
+    report
+        .issues
+        .sort_by(|a, b| b.severity.cmp(&a.severity).then(a.path.cmp(&b.path)));
+    report
+}
+
+fn issue(path: &Path, kind: IssueKind) -> VerifyIssue {
+    let severity = match kind {
+        IssueKind::Corrupted => Severity::Critical,
+        IssueKind::Unreadable => Severity::Warning,
+        IssueKind::Missing => Severity::Info,
+    };
+    VerifyIssue {
+        path: path.to_path_buf(),
+        kind,
+        severity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn file(path: &str, bytes: u64, modified: u64, hash: Option<&str>) -> FileMetadata {
+        FileMetadata {
+            path: PathBuf::from(path),
+            bytes,
+            modified_at: Some(UNIX_EPOCH + Duration::from_secs(modified)),
+            created_at: None,
+            accessed_at: None,
+            content_hash: hash.map(str::to_string),
+            hash_sampled: false,
+            disk_usage: None,
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let stored = vec![
+            file("intact", 1, 10, Some("a")),
+            file("rotten", 1, 10, Some("b")),
+            file("edited", 1, 10, Some("c")),
+            file("unreadable", 1, 10, Some("d")),
+            file("deleted", 1, 10, Some("e")),
+            file("unhashed", 1, 10, None),
+        ];
+        let live = vec![
+            file("intact", 1, 10, Some("a")),
+            file("rotten", 1, 10, Some("x")),
+            file("edited", 1, 20, Some("y")),
+            file("unreadable", 1, 10, None),
+            file("unhashed", 1, 10, Some("f")),
+            file("new", 1, 30, Some("g")),
+        ];
+
+        let report = verify(&live, &stored);
+        assert_eq!(
+            (
+                report.verified,
+                report.modified,
+                report.added,
+                report.skipped
+            ),
+            (1, 1, 1, 1)
+        );
+        let issues: Vec<(&str, IssueKind, Severity)> = report
+            .issues
+            .iter()
+            .map(|i| (i.path.to_str().unwrap(), i.kind, i.severity))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                ("rotten", IssueKind::Corrupted, Severity::Critical),
+                ("unreadable", IssueKind::Unreadable, Severity::Warning),
+                ("deleted", IssueKind::Missing, Severity::Info),
+            ]
+        );
+        assert_eq!(report.max_severity(), Some(Severity::Critical));
+    }
+}
@@ -0,0 +1,298 @@
+use rusqlite::{Connection, Result};
+use std::time::SystemTime;
+
+use crate::utils::{escape_bytes, to_nanos};
+
+/// How serious a finding of the integrity check is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Nothing was found
+    Ok,
+    /// Suspicious, but possibly legitimate (e.g. a snapshot of an empty directory)
+    Warning,
+    /// The database is inconsistent
+    Error,
+}
+
+/// The result of a single consistency check
+#[derive(Debug, serde::Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub severity: Severity,
+    /// Number of problems found
+    pub count: usize,
+    /// A description of (some of) the problems found
+    pub details: Vec<String>,
+    /// Whether `--repair` can fix the problems
+    pub repairable: bool,
+}
+
+/// Maximum number of details reported per check
+const MAX_DETAILS: usize = 20;
+
+/// Snapshots are allowed to be this far in the future (in seconds), to tolerate clock skew
+const FUTURE_TOLERANCE_SECS: i64 = 300;
+
+/// Runs every consistency check against the database
+pub fn check_database(conn: &Connection) -> Result<Vec<CheckResult>> {
+    Ok(vec![
+        check_integrity(conn)?,
+        check_foreign_keys(conn)?,
+        check_orphan_files(conn)?,
+        check_orphan_scan_errors(conn)?,
+        check_duplicate_paths(conn)?,
+        check_empty_snapshots(conn)?,
+        check_future_snapshots(conn)?,
+    ])
+}
+
+fn result(
+    name: &'static str,
+    severity: Severity,
+    details: Vec<String>,
+    repairable: bool,
+) -> CheckResult {
+    let count = details.len();
+    CheckResult {
+        name,
+        severity: if count == 0 { Severity::Ok } else { severity },
+        count,
+        details: details.into_iter().take(MAX_DETAILS).collect(),
+        repairable: count > 0 && repairable,
+    }
+}
+
+/// Collects the messages returned by a query. Messages that include a path that isn't valid UTF-8
+/// are escaped.
+fn collect_strings(conn: &Connection, sql: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(sql)?;
+    stmt.query_map([], |row| {
+        Ok(escape_bytes(row.get_ref(0)?.as_bytes()?).into_owned())
+    })?
+    .collect()
+}
+
+fn check_integrity(conn: &Connection) -> Result<CheckResult> {
+    let messages: Vec<String> = collect_strings(conn, "PRAGMA integrity_check")?
+        .into_iter()
+        .filter(|m| m != "ok")
+        .collect();
+    // Rebuilding the indexes fixes some of these problems, but not damaged table pages
+    Ok(result("integrity", Severity::Error, messages, false))
+}
+
+fn check_foreign_keys(conn: &Connection) -> Result<CheckResult> {
+    let violations = collect_strings(
+        conn,
+        "SELECT \"table\" || ' row ' || rowid || ' references a missing ' || parent
+        FROM pragma_foreign_key_check",
+    )?;
+    // Every foreign key references a snapshot or a root, whose orphans are removed by the repair
+    Ok(result("foreign keys", Severity::Error, violations, true))
+}
+
+fn check_orphan_files(conn: &Connection) -> Result<CheckResult> {
+    let orphans = collect_strings(
+        conn,
+        "SELECT 'file row ' || id || ' (' || path || ') references missing snapshot ' || snapshot_id
+        FROM files WHERE snapshot_id NOT IN (SELECT id FROM snapshots)",
+    )?;
+    Ok(result("orphan files", Severity::Error, orphans, true))
+}
+
+fn check_orphan_scan_errors(conn: &Connection) -> Result<CheckResult> {
+    let orphans = collect_strings(
+        conn,
+        "SELECT 'scan error row ' || id || ' (' || path || ') references missing snapshot ' || snapshot_id
+        FROM scan_errors WHERE snapshot_id NOT IN (SELECT id FROM snapshots)",
+    )?;
+    Ok(result("orphan scan errors", Severity::Error, orphans, true))
+}
+
+fn check_duplicate_paths(conn: &Connection) -> Result<CheckResult> {
+    let duplicates = collect_strings(
+        conn,
+        "SELECT 'snapshot ' || snapshot_id || ' lists ' || path || ' ' || COUNT(*) || ' times'
+        FROM files GROUP BY snapshot_id, path HAVING COUNT(*) > 1",
+    )?;
+    Ok(result("duplicate paths", Severity::Error, duplicates, true))
+}
+
+fn check_empty_snapshots(conn: &Connection) -> Result<CheckResult> {
+    let empty = collect_strings(
+        conn,
+        "SELECT 'snapshot ' || s.id || ' of ' || r.path || ' has no files'
+        FROM snapshots s JOIN roots r ON r.id = s.root_id
+        WHERE NOT EXISTS (SELECT 1 FROM files f WHERE f.snapshot_id = s.id)",
+    )?;
+    Ok(result("empty snapshots", Severity::Warning, empty, false))
+}
+
+fn check_future_snapshots(conn: &Connection) -> Result<CheckResult> {
+    let now = to_nanos(SystemTime::now()).unwrap_or_default();
+    let mut stmt = conn.prepare(
+        "SELECT 'snapshot ' || s.id || ' of ' || r.path || ' is dated '
+            || datetime(s.timestamp / 1000000000, 'unixepoch')
+        FROM snapshots s JOIN roots r ON r.id = s.root_id
+        WHERE s.timestamp > ?1",
+    )?;
+    let future = stmt
+        .query_map([now + FUTURE_TOLERANCE_SECS * 1_000_000_000], |row| {
+            Ok(escape_bytes(row.get_ref(0)?.as_bytes()?).into_owned())
+        })?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(result(
+        "future timestamps",
+        Severity::Warning,
+        future,
+        false,
+    ))
+}
+
+/// Fixes the repairable problems found by `check_database`, in a single transaction.
+/// Returns the number of rows removed.
+pub fn repair_database(conn: &mut Connection) -> Result<usize> {
+    let tx = conn.transaction()?;
+    let mut removed = 0;
+
+    // Snapshots that don't belong to any root, before the rows that belong to them
+    removed += tx.execute(
+        "DELETE FROM snapshots WHERE root_id NOT IN (SELECT id FROM roots)",
+        [],
+    )?;
+    // Files and scan errors that don't belong to any snapshot
+    removed += tx.execute(
+        "DELETE FROM files WHERE snapshot_id NOT IN (SELECT id FROM snapshots)",
+        [],
+    )?;
+    removed += tx.execute(
+        "DELETE FROM scan_errors WHERE snapshot_id NOT IN (SELECT id FROM snapshots)",
+        [],
+    )?;
+    // Keep the first row of every duplicated path
+    removed += tx.execute(
+        "DELETE FROM files WHERE id NOT IN (SELECT MIN(id) FROM files GROUP BY snapshot_id, path)",
+        [],
+    )?;
+    // Rebuild the indexes, which fixes most index corruption reported by the integrity check
+    tx.execute_batch("REINDEX")?;
+
+    tx.commit()?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{initialize_schema, insert_snapshot};
+    use crate::models::{FileMetadata, Snapshot};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn create_in_memory_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        conn
+    }
+
+    fn snapshot(timestamp: SystemTime, files: Vec<&str>) -> Snapshot {
+        Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp,
+            git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
+            files: files
+                .into_iter()
+                .map(|path| FileMetadata {
+                    path: PathBuf::from(path),
+                    bytes: 1,
+                    modified_at: None,
+                    created_at: None,
+                    accessed_at: None,
+                    content_hash: None,
+                    hash_sampled: false,
+                    disk_usage: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn severity_of(results: &[CheckResult], name: &str) -> (Severity, usize) {
+        let result = results.iter().find(|r| r.name == name).unwrap();
+        (result.severity, result.count)
+    }
+
+    #[test]
+    fn test_check_clean_database() {
+        let mut conn = create_in_memory_db();
+        insert_snapshot(&mut conn, &snapshot(SystemTime::now(), vec!["a", "b"])).unwrap();
+
+        let results = check_database(&conn).unwrap();
+        assert!(results.iter().all(|r| r.severity == Severity::Ok));
+    }
+
+    #[test]
+    fn test_check_and_repair() {
+        let mut conn = create_in_memory_db();
+        let id = insert_snapshot(&mut conn, &snapshot(SystemTime::now(), vec!["a", "a"])).unwrap();
+        insert_snapshot(&mut conn, &snapshot(SystemTime::now(), vec![])).unwrap();
+        let future = SystemTime::now() + Duration::from_secs(86_400);
+        insert_snapshot(&mut conn, &snapshot(future, vec!["c"])).unwrap();
+
+        // Simulate rows written while foreign keys were not enforced
+        conn.pragma_update(None, "foreign_keys", false).unwrap();
+        conn.execute(
+            "INSERT INTO files (snapshot_id, path, bytes) VALUES (999, 'orphan', 1)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO scan_errors (snapshot_id, path, error) VALUES (999, 'locked', 'denied')",
+            [],
+        )
+        .unwrap();
+        conn.pragma_update(None, "foreign_keys", true).unwrap();
+
+        let results = check_database(&conn).unwrap();
+        assert_eq!(severity_of(&results, "orphan files"), (Severity::Error, 1));
+        assert_eq!(
+            severity_of(&results, "orphan scan errors"),
+            (Severity::Error, 1)
+        );
+        assert_eq!(
+            severity_of(&results, "duplicate paths"),
+            (Severity::Error, 1)
+        );
+        assert_eq!(
+            severity_of(&results, "empty snapshots"),
+            (Severity::Warning, 1)
+        );
+        assert_eq!(
+            severity_of(&results, "future timestamps"),
+            (Severity::Warning, 1)
+        );
+
+        assert_eq!(repair_database(&mut conn).unwrap(), 3);
+
+        let results = check_database(&conn).unwrap();
+        assert_eq!(severity_of(&results, "orphan files"), (Severity::Ok, 0));
+        assert_eq!(
+            severity_of(&results, "orphan scan errors"),
+            (Severity::Ok, 0)
+        );
+        assert_eq!(severity_of(&results, "duplicate paths"), (Severity::Ok, 0));
+        assert_eq!(severity_of(&results, "foreign keys"), (Severity::Ok, 0));
+        let remaining: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM files WHERE snapshot_id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(remaining, 1);
+    }
+}
@@ -1,91 +1,149 @@
-use crate::models::{Diff, FileMetadata};
+use super::{
+    find_root_id, get_files_for_snapshot, get_latest_snapshot_id, get_scan_stats,
+    get_snapshot_hashing,
+};
+use crate::models::{Change, Diff, FileMetadata, ScanError};
+use crate::utils::PathNormalization;
+use crate::utils::hashing::{HashAlgorithm, HashMode};
 use rusqlite::{Connection, Result};
-use std::collections::{HashMap, HashSet};
-
-pub fn compute_diff(conn: &mut Connection, root: &str, files: &[FileMetadata]) -> Result<Diff> {
-    use rusqlite::OptionalExtension;
-
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Computes the changes between the given files, hashed with `hash_algorithm` in `hash_mode`,
+/// and the latest snapshot of the root, if it is known.
+///
+/// If the latest snapshot was hashed with another algorithm, or either side wasn't hashed, the
+/// hashes can't be compared: files are then considered modified when their size or modification
+/// time changed. Files that could not be read are reported as unreadable, including those missing
+/// on one side because they are covered by its scan errors (`errors` for the current files). Paths
+/// are matched under `normalization`; files present on both sides are reported with their current
+/// path.
+pub fn compute_diff(
+    conn: &Connection,
+    root_id: Option<i64>,
+    files: &[FileMetadata],
+    errors: &[ScanError],
+    hash_algorithm: HashAlgorithm,
+    hash_mode: HashMode,
+    normalization: PathNormalization,
+) -> Result<Diff> {
     // Get last snapshot
-    let last_id: Option<i64> = conn
-        .query_row(
-            "SELECT id FROM snapshots WHERE root = ?1 ORDER BY timestamp DESC LIMIT 1",
-            [root],
-            |row| row.get(0),
-        )
-        .optional()?;
+    let last_id = match root_id {
+        Some(root_id) => get_latest_snapshot_id(conn, root_id)?,
+        None => None,
+    };
 
     let last_id = match last_id {
         Some(id) => id,
         None => {
             // No previous snapshots -> everything is new
             return Ok(Diff {
-                added: files
-                    .iter()
-                    .map(|f| f.path.to_string_lossy().to_string())
-                    .collect(),
+                added: files.iter().map(|f| f.path.clone()).collect(),
                 removed: vec![],
                 modified: vec![],
+                unreadable: vec![],
             });
         }
     };
 
-    // Load previous files: path -> content_hash
-    let mut stmt = conn.prepare("SELECT path, content_hash FROM files WHERE snapshot_id = ?1")?;
-    let previous_files: HashMap<String, Option<String>> = stmt
-        .query_map([last_id], |row| {
-            let path: String = row.get(0)?;
-            let content_hash: Option<String> = row.get(1)?;
-            Ok((path, content_hash))
-        })?
-        .collect::<Result<_>>()?;
+    let (previous_algorithm, previous_mode) =
+        get_snapshot_hashing(conn, last_id)?.unwrap_or_default();
+    let hashed = previous_mode != HashMode::Metadata && hash_mode != HashMode::Metadata;
+    let compare_hashes = hashed && previous_algorithm == hash_algorithm;
+    if hashed && !compare_hashes {
+        log::warn!(
+            "Snapshot {last_id} was hashed with {previous_algorithm}, not {hash_algorithm}: comparing sizes and modification times instead"
+        );
+    }
+
+    // Load previous files by path
+    let previous = get_files_for_snapshot(conn, last_id)?;
+    let previous_files = normalization.index(&previous, |f| &f.path);
+    let current_files = normalization.index(files, |f| &f.path);
 
     let mut added = Vec::new();
     let mut removed = Vec::new();
     let mut modified = Vec::new();
-
-    let current_paths: HashSet<String> = files
-        .iter()
-        .map(|f| f.path.to_string_lossy().to_string())
-        .collect();
-    let previous_paths: HashSet<String> = previous_files.keys().cloned().collect();
+    let mut unreadable = Vec::new();
 
     // Added files
-    for f in current_paths.difference(&previous_paths) {
-        added.push(f.clone());
+    for (key, f) in &current_files {
+        if !previous_files.contains_key(key) {
+            added.push(f.path.clone());
+        }
     }
 
     // Removed files
-    for f in previous_paths.difference(&current_paths) {
-        removed.push(f.clone());
+    for (key, f) in &previous_files {
+        if !current_files.contains_key(key) {
+            removed.push(f.path.clone());
+        }
     }
 
-    // Modified files (present in both, different hash)
-    for f in current_paths.intersection(&previous_paths) {
-        let new_file = files
-            .iter()
-            .find(|x| x.path.to_string_lossy() == *f)
-            .unwrap();
-        let old_hash = previous_files.get(f).unwrap();
-        if old_hash != &new_file.content_hash {
-            modified.push(f.clone());
+    // Modified files (present in both, different content)
+    for (key, new_file) in &current_files {
+        let Some(old_file) = previous_files.get(key) else {
+            continue;
+        };
+        match new_file.compare(old_file, compare_hashes) {
+            Change::Unchanged => {}
+            Change::Modified => modified.push(new_file.path.clone()),
+            Change::Unknown => unreadable.push(new_file.path.clone()),
         }
     }
 
-    Ok(Diff {
+    let mut diff = Diff {
         added,
         removed,
         modified,
-    })
+        unreadable,
+    };
+    let previous_errors = get_scan_stats(conn, last_id)?
+        .map(|stats| stats.errors)
+        .unwrap_or_default();
+    diff.account_for_errors(&previous_errors, errors);
+    Ok(diff)
+}
+
+/// Counts the files whose hash was sampled in the latest snapshot of the root and is now
+/// computed over the whole content, with the same algorithm.
+pub fn count_hash_upgrades(
+    conn: &Connection,
+    root: &Path,
+    files: &[FileMetadata],
+    hash_algorithm: HashAlgorithm,
+) -> Result<usize> {
+    let last_id = match find_root_id(conn, root)? {
+        Some(root_id) => get_latest_snapshot_id(conn, root_id)?,
+        None => None,
+    };
+    let Some(last_id) = last_id else {
+        return Ok(0);
+    };
+    let (previous_algorithm, _) = get_snapshot_hashing(conn, last_id)?.unwrap_or_default();
+    if previous_algorithm != hash_algorithm {
+        return Ok(0);
+    }
+
+    let sampled: HashSet<_> = get_files_for_snapshot(conn, last_id)?
+        .into_iter()
+        .filter(|f| f.hash_sampled)
+        .map(|f| f.path)
+        .collect();
+    Ok(files
+        .iter()
+        .filter(|f| f.content_hash.is_some() && !f.hash_sampled && sampled.contains(&f.path))
+        .count())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::database::{initialize_schema, insert_snapshot};
-    use crate::models::{FileMetadata, Snapshot};
+    use crate::models::{FileMetadata, ScanStats, Snapshot};
     use rusqlite::Connection;
     use std::path::PathBuf;
-    use std::time::SystemTime;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
     fn create_in_memory_db() -> Connection {
         let mut conn = Connection::open_in_memory().unwrap();
@@ -95,7 +153,7 @@ mod tests {
 
     #[test]
     fn test_compute_diff_no_previous_snapshot() {
-        let mut conn = create_in_memory_db();
+        let conn = create_in_memory_db();
         let files = vec![FileMetadata {
             path: PathBuf::from("file1.txt"),
             bytes: 123,
@@ -103,11 +161,22 @@ mod tests {
             created_at: None,
             accessed_at: None,
             content_hash: Some("hash1".to_string()),
+            hash_sampled: false,
+            disk_usage: None,
         }];
 
-        let diff = compute_diff(&mut conn, "/tmp", &files).unwrap();
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
 
-        assert_eq!(diff.added, vec!["file1.txt"]);
+        assert_eq!(diff.added, vec![PathBuf::from("file1.txt")]);
         assert!(diff.removed.is_empty());
         assert!(diff.modified.is_empty());
     }
@@ -122,16 +191,31 @@ mod tests {
             created_at: None,
             accessed_at: None,
             content_hash: Some("hash1".to_string()),
+            hash_sampled: false,
+            disk_usage: None,
         }];
         let snapshot = Snapshot {
             root: PathBuf::from("/tmp"),
             timestamp: SystemTime::now(),
             files: files.clone(),
             git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
         };
         insert_snapshot(&mut conn, &snapshot).unwrap();
 
-        let diff = compute_diff(&mut conn, "/tmp", &files).unwrap();
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
 
         assert!(diff.is_empty());
     }
@@ -145,6 +229,10 @@ mod tests {
             timestamp: SystemTime::now(),
             files: initial_files,
             git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
         };
         insert_snapshot(&mut conn, &snapshot).unwrap();
 
@@ -155,11 +243,22 @@ mod tests {
             created_at: None,
             accessed_at: None,
             content_hash: Some("hash1".to_string()),
+            hash_sampled: false,
+            disk_usage: None,
         }];
 
-        let diff = compute_diff(&mut conn, "/tmp", &new_files).unwrap();
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &new_files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
 
-        assert_eq!(diff.added, vec!["file1.txt"]);
+        assert_eq!(diff.added, vec![PathBuf::from("file1.txt")]);
         assert!(diff.removed.is_empty());
         assert!(diff.modified.is_empty());
     }
@@ -174,21 +273,36 @@ mod tests {
             created_at: None,
             accessed_at: None,
             content_hash: Some("hash1".to_string()),
+            hash_sampled: false,
+            disk_usage: None,
         }];
         let snapshot = Snapshot {
             root: PathBuf::from("/tmp"),
             timestamp: SystemTime::now(),
             files: initial_files,
             git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
         };
         insert_snapshot(&mut conn, &snapshot).unwrap();
 
         let new_files = vec![];
 
-        let diff = compute_diff(&mut conn, "/tmp", &new_files).unwrap();
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &new_files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
 
         assert!(diff.added.is_empty());
-        assert_eq!(diff.removed, vec!["file1.txt"]);
+        assert_eq!(diff.removed, vec![PathBuf::from("file1.txt")]);
         assert!(diff.modified.is_empty());
     }
 
@@ -202,12 +316,18 @@ mod tests {
             created_at: None,
             accessed_at: None,
             content_hash: Some("hash1".to_string()),
+            hash_sampled: false,
+            disk_usage: None,
         }];
         let snapshot = Snapshot {
             root: PathBuf::from("/tmp"),
             timestamp: SystemTime::now(),
             files: initial_files,
             git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
         };
         insert_snapshot(&mut conn, &snapshot).unwrap();
 
@@ -218,13 +338,24 @@ mod tests {
             created_at: None,
             accessed_at: None,
             content_hash: Some("hash2".to_string()),
+            hash_sampled: false,
+            disk_usage: None,
         }];
 
-        let diff = compute_diff(&mut conn, "/tmp", &new_files).unwrap();
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &new_files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
 
         assert!(diff.added.is_empty());
         assert!(diff.removed.is_empty());
-        assert_eq!(diff.modified, vec!["file1.txt"]);
+        assert_eq!(diff.modified, vec![PathBuf::from("file1.txt")]);
     }
 
     #[test]
@@ -238,6 +369,8 @@ mod tests {
                 created_at: None,
                 accessed_at: None,
                 content_hash: Some("hash1".to_string()),
+                hash_sampled: false,
+                disk_usage: None,
             },
             FileMetadata {
                 path: PathBuf::from("file_to_modify.txt"),
@@ -246,6 +379,8 @@ mod tests {
                 created_at: None,
                 accessed_at: None,
                 content_hash: Some("hash2".to_string()),
+                hash_sampled: false,
+                disk_usage: None,
             },
             FileMetadata {
                 path: PathBuf::from("file_to_remove.txt"),
@@ -254,6 +389,8 @@ mod tests {
                 created_at: None,
                 accessed_at: None,
                 content_hash: Some("hash3".to_string()),
+                hash_sampled: false,
+                disk_usage: None,
             },
         ];
         let snapshot = Snapshot {
@@ -261,6 +398,10 @@ mod tests {
             timestamp: SystemTime::now(),
             files: initial_files,
             git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
         };
         insert_snapshot(&mut conn, &snapshot).unwrap();
 
@@ -272,6 +413,8 @@ mod tests {
                 created_at: None,
                 accessed_at: None,
                 content_hash: Some("hash1".to_string()),
+                hash_sampled: false,
+                disk_usage: None,
             },
             FileMetadata {
                 path: PathBuf::from("file_to_modify.txt"),
@@ -280,6 +423,8 @@ mod tests {
                 created_at: None,
                 accessed_at: None,
                 content_hash: Some("new_hash".to_string()),
+                hash_sampled: false,
+                disk_usage: None,
             },
             FileMetadata {
                 path: PathBuf::from("file_to_add.txt"),
@@ -288,13 +433,490 @@ mod tests {
                 created_at: None,
                 accessed_at: None,
                 content_hash: Some("hash4".to_string()),
+                hash_sampled: false,
+                disk_usage: None,
             },
         ];
 
-        let diff = compute_diff(&mut conn, "/tmp", &new_files).unwrap();
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &new_files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
+
+        assert_eq!(diff.added, vec![PathBuf::from("file_to_add.txt")]);
+        assert_eq!(diff.removed, vec![PathBuf::from("file_to_remove.txt")]);
+        assert_eq!(diff.modified, vec![PathBuf::from("file_to_modify.txt")]);
+    }
+
+    #[test]
+    fn test_compute_diff_different_hash_algorithms() {
+        let mut conn = create_in_memory_db();
+        let file = |name: &str, bytes: u64, hash: &str| FileMetadata {
+            path: PathBuf::from(name),
+            bytes,
+            modified_at: None,
+            created_at: None,
+            accessed_at: None,
+            content_hash: Some(hash.to_string()),
+            hash_sampled: false,
+            disk_usage: None,
+        };
+        let snapshot = Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp: SystemTime::now(),
+            files: vec![
+                file("same.txt", 1, "blake3-a"),
+                file("grown.txt", 1, "blake3-b"),
+            ],
+            git_commit_hash: None,
+            hash_algorithm: HashAlgorithm::Blake3,
+            hash_mode: HashMode::Full,
+            atime_preserved: false,
+            scan_stats: None,
+        };
+        insert_snapshot(&mut conn, &snapshot).unwrap();
+
+        // Every hash differs, only the size change counts
+        let new_files = vec![
+            file("same.txt", 1, "xxh3-a"),
+            file("grown.txt", 2, "xxh3-b"),
+        ];
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &new_files,
+            &[],
+            HashAlgorithm::Xxh3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
+
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.modified, vec![PathBuf::from("grown.txt")]);
+    }
+
+    #[test]
+    fn test_compute_diff_without_hashes() {
+        let mut conn = create_in_memory_db();
+        let file = |name: &str, bytes: u64, modified: u64| FileMetadata {
+            path: PathBuf::from(name),
+            bytes,
+            modified_at: Some(UNIX_EPOCH + Duration::from_secs(modified)),
+            created_at: None,
+            accessed_at: None,
+            content_hash: None,
+            hash_sampled: false,
+            disk_usage: None,
+        };
+        let snapshot = Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp: SystemTime::now(),
+            files: vec![
+                file("same.txt", 1, 100),
+                file("touched.txt", 1, 100),
+                file("grown.txt", 1, 100),
+            ],
+            git_commit_hash: None,
+            hash_algorithm: HashAlgorithm::Blake3,
+            hash_mode: HashMode::Metadata,
+            atime_preserved: false,
+            scan_stats: None,
+        };
+        insert_snapshot(&mut conn, &snapshot).unwrap();
+
+        let new_files = vec![
+            file("same.txt", 1, 100),
+            file("touched.txt", 1, 200),
+            file("grown.txt", 2, 100),
+        ];
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &new_files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
+
+        let mut modified = diff.modified.clone();
+        modified.sort();
+        assert_eq!(
+            modified,
+            vec![PathBuf::from("grown.txt"), PathBuf::from("touched.txt")]
+        );
+    }
+
+    #[test]
+    fn test_compute_diff_sampled_hashes() {
+        let mut conn = create_in_memory_db();
+        let file = |name: &str, modified: u64, hash: &str, sampled: bool| FileMetadata {
+            path: PathBuf::from(name),
+            bytes: 1,
+            modified_at: Some(UNIX_EPOCH + Duration::from_secs(modified)),
+            created_at: None,
+            accessed_at: None,
+            content_hash: Some(hash.to_string()),
+            hash_sampled: sampled,
+            disk_usage: None,
+        };
+        let snapshot = Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp: SystemTime::now(),
+            files: vec![
+                file("same.img", 100, "a", true),
+                file("touched.img", 100, "b", true),
+                file("rewritten.img", 100, "c", true),
+                file("upgraded.img", 100, "d", true),
+            ],
+            git_commit_hash: None,
+            hash_algorithm: HashAlgorithm::Blake3,
+            hash_mode: HashMode::Sampled { above: 0 },
+            atime_preserved: false,
+            scan_stats: None,
+        };
+        insert_snapshot(&mut conn, &snapshot).unwrap();
+
+        let new_files = vec![
+            file("same.img", 100, "a", true),
+            // Same samples, but the modification time changed
+            file("touched.img", 200, "b", true),
+            // Different samples prove a change, even with the same metadata
+            file("rewritten.img", 100, "x", true),
+            // A full hash can't be compared with a sampled one
+            file("upgraded.img", 100, "full", false),
+        ];
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &new_files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
+        let mut modified = diff.modified.clone();
+        modified.sort();
+        assert_eq!(
+            modified,
+            vec![PathBuf::from("rewritten.img"), PathBuf::from("touched.img")]
+        );
+
+        let upgrades =
+            count_hash_upgrades(&conn, Path::new("/tmp"), &new_files, HashAlgorithm::Blake3)
+                .unwrap();
+        assert_eq!(upgrades, 1);
+        let upgrades =
+            count_hash_upgrades(&conn, Path::new("/tmp"), &new_files, HashAlgorithm::Xxh3).unwrap();
+        assert_eq!(upgrades, 0);
+    }
+
+    #[test]
+    fn test_compute_diff_unreadable_files() {
+        let mut conn = create_in_memory_db();
+        let file = |name: &str, modified: u64, hash: Option<&str>| FileMetadata {
+            path: PathBuf::from(name),
+            bytes: 1,
+            modified_at: Some(UNIX_EPOCH + Duration::from_secs(modified)),
+            created_at: None,
+            accessed_at: None,
+            content_hash: hash.map(str::to_string),
+            hash_sampled: false,
+            disk_usage: None,
+        };
+        let snapshot = Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp: SystemTime::now(),
+            files: vec![
+                file("locked.txt", 100, Some("a")),
+                file("touched.txt", 100, Some("b")),
+                file("recovered.txt", 100, None),
+            ],
+            git_commit_hash: None,
+            hash_algorithm: HashAlgorithm::Blake3,
+            hash_mode: HashMode::Full,
+            atime_preserved: false,
+            scan_stats: None,
+        };
+        insert_snapshot(&mut conn, &snapshot).unwrap();
+
+        let new_files = vec![
+            // Same metadata, but the content couldn't be read
+            file("locked.txt", 100, None),
+            // The metadata proves a change even without a hash
+            file("touched.txt", 200, None),
+            // Readable again
+            file("recovered.txt", 100, Some("c")),
+        ];
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &new_files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
+        assert_eq!(diff.modified, vec![PathBuf::from("touched.txt")]);
+        let mut unreadable = diff.unreadable.clone();
+        unreadable.sort();
+        assert_eq!(
+            unreadable,
+            vec![PathBuf::from("locked.txt"), PathBuf::from("recovered.txt")]
+        );
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn test_compute_diff_skipped_files() {
+        let mut conn = create_in_memory_db();
+        let file = |name: &str| FileMetadata {
+            path: PathBuf::from(name),
+            bytes: 1,
+            modified_at: Some(UNIX_EPOCH + Duration::from_secs(100)),
+            created_at: None,
+            accessed_at: None,
+            content_hash: Some(name.to_string()),
+            hash_sampled: false,
+            disk_usage: None,
+        };
+        let error = |path: &str| ScanError {
+            path: PathBuf::from(path),
+            error: "Permission denied".to_string(),
+        };
+        let all_files = vec![
+            file("a.txt"),
+            file("locked.txt"),
+            file("private/b.txt"),
+            file("gone.txt"),
+        ];
+        let snapshot = Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp: SystemTime::now(),
+            files: all_files.clone(),
+            git_commit_hash: None,
+            hash_algorithm: HashAlgorithm::Blake3,
+            hash_mode: HashMode::Full,
+            atime_preserved: false,
+            scan_stats: None,
+        };
+        insert_snapshot(&mut conn, &snapshot).unwrap();
+
+        // Files left out of the scan because they couldn't be read aren't removed
+        let errors = vec![error("locked.txt"), error("private")];
+        let scanned = vec![file("a.txt")];
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &scanned,
+            &errors,
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
+        assert_eq!(diff.removed, vec![PathBuf::from("gone.txt")]);
+        let mut unreadable = diff.unreadable.clone();
+        unreadable.sort();
+        assert_eq!(
+            unreadable,
+            vec![PathBuf::from("locked.txt"), PathBuf::from("private/b.txt")]
+        );
+
+        // Nor added once they can be read again
+        let snapshot = Snapshot {
+            files: scanned,
+            scan_stats: Some(ScanStats {
+                errors,
+                ..Default::default()
+            }),
+            ..snapshot
+        };
+        insert_snapshot(&mut conn, &snapshot).unwrap();
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &all_files[..3],
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.unreadable.len(), 2);
+    }
+
+    #[test]
+    fn test_compute_diff_subsecond_times() {
+        let mut conn = create_in_memory_db();
+        let file = |name: &str, modified: Duration| FileMetadata {
+            path: PathBuf::from(name),
+            bytes: 1,
+            modified_at: Some(UNIX_EPOCH + modified),
+            created_at: None,
+            accessed_at: None,
+            content_hash: None,
+            hash_sampled: false,
+            disk_usage: None,
+        };
+        let snapshot = Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp: SystemTime::now(),
+            files: vec![
+                file("same.txt", Duration::new(100, 500)),
+                file("touched.txt", Duration::new(100, 500)),
+                // Recorded before nanoseconds were stored
+                file("legacy.txt", Duration::from_secs(100)),
+            ],
+            git_commit_hash: None,
+            hash_algorithm: HashAlgorithm::Blake3,
+            hash_mode: HashMode::Metadata,
+            atime_preserved: false,
+            scan_stats: None,
+        };
+        insert_snapshot(&mut conn, &snapshot).unwrap();
+
+        let new_files = vec![
+            file("same.txt", Duration::new(100, 500)),
+            file("touched.txt", Duration::new(100, 900)),
+            file("legacy.txt", Duration::new(100, 900)),
+        ];
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &new_files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Metadata,
+            PathNormalization::Exact,
+        )
+        .unwrap();
+        assert_eq!(diff.modified, vec![PathBuf::from("touched.txt")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compute_diff_non_utf8_paths() {
+        use crate::database::get_files_for_snapshot;
+        use crate::utils::path_from_bytes;
+
+        let mut conn = create_in_memory_db();
+        // Both names would decode lossily to the same string
+        let file = |name: &[u8]| FileMetadata {
+            path: path_from_bytes(name.to_vec()),
+            bytes: 1,
+            modified_at: Some(UNIX_EPOCH + Duration::from_secs(100)),
+            created_at: None,
+            accessed_at: None,
+            content_hash: Some("a".to_string()),
+            hash_sampled: false,
+            disk_usage: None,
+        };
+        let files = vec![file(b"bad\xff.txt"), file(b"bad\xfe.txt")];
+        let snapshot = Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp: SystemTime::now(),
+            files: files.clone(),
+            git_commit_hash: None,
+            hash_algorithm: HashAlgorithm::Blake3,
+            hash_mode: HashMode::Full,
+            atime_preserved: false,
+            scan_stats: None,
+        };
+        let snapshot_id = insert_snapshot(&mut conn, &snapshot).unwrap();
+
+        let mut stored: Vec<_> = get_files_for_snapshot(&conn, snapshot_id)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+        stored.sort();
+        assert_eq!(stored, vec![files[1].path.clone(), files[0].path.clone()]);
+
+        let diff = compute_diff(
+            &conn,
+            find_root_id(&conn, Path::new("/tmp")).unwrap(),
+            &files,
+            &[],
+            HashAlgorithm::Blake3,
+            HashMode::Full,
+            PathNormalization::Exact,
+        )
+        .unwrap();
+        assert!(diff.is_empty());
+        assert!(diff.unreadable.is_empty());
+    }
+
+    #[test]
+    fn test_compute_diff_normalized_paths() {
+        let mut conn = create_in_memory_db();
+        let file = |name: &str, hash: &str| FileMetadata {
+            path: PathBuf::from(name),
+            bytes: 1,
+            modified_at: Some(UNIX_EPOCH + Duration::from_secs(100)),
+            created_at: None,
+            accessed_at: None,
+            content_hash: Some(hash.to_string()),
+            hash_sampled: false,
+            disk_usage: None,
+        };
+        let snapshot = Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp: SystemTime::now(),
+            files: vec![
+                file("caf\u{e9}.txt", "a"),
+                file("R\u{e9}sum\u{e9}.txt", "b"),
+            ],
+            git_commit_hash: None,
+            hash_algorithm: HashAlgorithm::Blake3,
+            hash_mode: HashMode::Full,
+            atime_preserved: false,
+            scan_stats: None,
+        };
+        insert_snapshot(&mut conn, &snapshot).unwrap();
+
+        // The same names, decomposed (NFD) and with a different case
+        let new_files = vec![
+            file("cafe\u{301}.txt", "a"),
+            file("RE\u{301}SUME\u{301}.txt", "changed"),
+        ];
+        let diff = |normalization| {
+            compute_diff(
+                &conn,
+                find_root_id(&conn, Path::new("/tmp")).unwrap(),
+                &new_files,
+                &[],
+                HashAlgorithm::Blake3,
+                HashMode::Full,
+                normalization,
+            )
+            .unwrap()
+        };
+
+        let exact = diff(PathNormalization::Exact);
+        assert_eq!(exact.added.len(), 2);
+        assert_eq!(exact.removed.len(), 2);
+
+        let nfc = diff(PathNormalization::Nfc);
+        assert_eq!(nfc.added, vec![new_files[1].path.clone()]);
+        assert_eq!(nfc.removed, vec![PathBuf::from("R\u{e9}sum\u{e9}.txt")]);
+        assert!(nfc.modified.is_empty());
 
-        assert_eq!(diff.added, vec!["file_to_add.txt"]);
-        assert_eq!(diff.removed, vec!["file_to_remove.txt"]);
-        assert_eq!(diff.modified, vec!["file_to_modify.txt"]);
+        let casefold = diff(PathNormalization::Casefold);
+        assert!(casefold.added.is_empty());
+        assert!(casefold.removed.is_empty());
+        assert_eq!(casefold.modified, vec![new_files[1].path.clone()]);
     }
 }
@@ -0,0 +1,97 @@
+use rusqlite::{Connection, Result};
+
+/// Deletes the given snapshots along with their files, in a single transaction.
+/// Returns the number of snapshots deleted.
+pub fn delete_snapshots(conn: &mut Connection, snapshot_ids: &[i64]) -> Result<usize> {
+    let tx = conn.transaction()?;
+    let mut deleted = 0;
+    for snapshot_id in snapshot_ids {
+        // Files are removed by the cascading foreign key
+        deleted += tx.execute("DELETE FROM snapshots WHERE id = ?1", [snapshot_id])?;
+    }
+    tx.commit()?;
+    Ok(deleted)
+}
+
+/// Forgets a root entirely: its identity, snapshots and files, in a single transaction.
+/// Returns the number of snapshots deleted.
+pub fn forget_root(conn: &mut Connection, root_id: i64) -> Result<usize> {
+    let tx = conn.transaction()?;
+    let snapshots: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM snapshots WHERE root_id = ?1",
+        [root_id],
+        |row| row.get(0),
+    )?;
+    // Snapshots and files are removed by the cascading foreign keys
+    tx.execute("DELETE FROM roots WHERE id = ?1", [root_id])?;
+    tx.commit()?;
+    Ok(snapshots as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{find_root_id, initialize_schema, insert_snapshot, list_snapshot_ids};
+    use crate::models::{FileMetadata, Snapshot};
+    use std::path::{Path, PathBuf};
+    use std::time::SystemTime;
+
+    fn create_in_memory_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        conn
+    }
+
+    fn snapshot(root: &str) -> Snapshot {
+        Snapshot {
+            root: PathBuf::from(root),
+            timestamp: SystemTime::now(),
+            git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
+            files: vec![FileMetadata {
+                path: PathBuf::from("file1.txt"),
+                bytes: 123,
+                modified_at: None,
+                created_at: None,
+                accessed_at: None,
+                content_hash: Some("hash1".to_string()),
+                hash_sampled: false,
+                disk_usage: None,
+            }],
+        }
+    }
+
+    fn count_files(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM files", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_delete_snapshots_cascades_to_files() {
+        let mut conn = create_in_memory_db();
+        let id1 = insert_snapshot(&mut conn, &snapshot("/tmp")).unwrap();
+        let id2 = insert_snapshot(&mut conn, &snapshot("/tmp")).unwrap();
+
+        assert_eq!(delete_snapshots(&mut conn, &[id1, 999]).unwrap(), 1);
+        assert_eq!(list_snapshot_ids(&conn, None).unwrap(), vec![id2]);
+        assert_eq!(count_files(&conn), 1);
+    }
+
+    #[test]
+    fn test_forget_root_cascades() {
+        let mut conn = create_in_memory_db();
+        insert_snapshot(&mut conn, &snapshot("/a")).unwrap();
+        insert_snapshot(&mut conn, &snapshot("/a")).unwrap();
+        let kept = insert_snapshot(&mut conn, &snapshot("/b")).unwrap();
+
+        let root_id = find_root_id(&conn, Path::new("/a")).unwrap().unwrap();
+        assert_eq!(forget_root(&mut conn, root_id).unwrap(), 2);
+
+        assert_eq!(find_root_id(&conn, Path::new("/a")).unwrap(), None);
+        assert_eq!(list_snapshot_ids(&conn, None).unwrap(), vec![kept]);
+        assert_eq!(count_files(&conn), 1);
+    }
+}
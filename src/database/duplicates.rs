@@ -0,0 +1,210 @@
+use rusqlite::{Connection, Result};
+
+use crate::models::{DuplicateFile, DuplicateGroup};
+use crate::utils::hashing::HashAlgorithm;
+use crate::utils::path_from_bytes;
+
+/// Groups the files of the given snapshots by content hash (and hash algorithm), keeping the
+/// groups with more than one copy.
+///
+/// Empty files, files without a hash and files with a sampled hash (which doesn't identify the
+/// content) are ignored. Files hardlinked to each other within a snapshot share their data: they
+/// are listed, but only count once towards the wasted bytes. Inode numbers are not compared across
+/// snapshots, which may come from other hosts. Groups are sorted by wasted bytes, largest first.
+pub fn find_duplicates(conn: &Connection, snapshot_ids: &[i64]) -> Result<Vec<DuplicateGroup>> {
+    if snapshot_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ids = snapshot_ids
+        .iter()
+        .map(i64::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let mut stmt = conn.prepare(&format!(
+        "WITH candidates AS (
+            SELECT s.id AS snapshot_id, s.hash_algorithm, f.content_hash, f.bytes, r.path AS root, f.path,
+                f.device, f.inode
+            FROM files f
+            JOIN snapshots s ON s.id = f.snapshot_id
+            JOIN roots r ON r.id = s.root_id
+            WHERE f.snapshot_id IN ({ids}) AND f.content_hash IS NOT NULL AND NOT f.hash_sampled AND f.bytes > 0
+        ),
+        groups AS (
+            SELECT hash_algorithm, content_hash, MAX(bytes) AS bytes,
+                -- Copies of the data: distinct inodes of each snapshot, and each file whose inode is
+                -- unknown
+                COUNT(DISTINCT CASE WHEN inode IS NOT NULL THEN snapshot_id || ':' || device || ':' || inode END)
+                    + SUM(inode IS NULL) AS copies
+            FROM candidates
+            GROUP BY hash_algorithm, content_hash
+            HAVING COUNT(*) > 1
+        )
+        SELECT g.hash_algorithm, g.content_hash, g.bytes, g.bytes * (g.copies - 1) AS wasted, c.root, c.path
+        FROM groups g
+        JOIN candidates c ON c.hash_algorithm = g.hash_algorithm AND c.content_hash = g.content_hash
+        ORDER BY wasted DESC, g.hash_algorithm, g.content_hash, c.root, c.path"
+    ))?;
+    let mut rows = stmt.query([])?;
+
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let hash_algorithm: HashAlgorithm = row.get(0)?;
+        let content_hash: String = row.get(1)?;
+        let file = DuplicateFile {
+            root: path_from_bytes(row.get(4)?),
+            path: path_from_bytes(row.get(5)?),
+        };
+        match groups.last_mut() {
+            Some(group)
+                if group.hash_algorithm == hash_algorithm && group.content_hash == content_hash =>
+            {
+                group.files.push(file)
+            }
+            _ => groups.push(DuplicateGroup {
+                hash_algorithm,
+                content_hash,
+                bytes: row.get(2)?,
+                wasted_bytes: row.get(3)?,
+                files: vec![file],
+            }),
+        }
+    }
+
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{initialize_schema, insert_snapshot, list_latest_snapshot_ids};
+    use crate::models::{DiskUsage, FileMetadata, Snapshot};
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn create_in_memory_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        conn
+    }
+
+    fn snapshot(root: &str, timestamp: u64, files: &[(&str, u64, &str)]) -> Snapshot {
+        Snapshot {
+            root: PathBuf::from(root),
+            timestamp: UNIX_EPOCH + Duration::from_secs(timestamp),
+            git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
+            files: files
+                .iter()
+                .map(|(path, bytes, hash)| FileMetadata {
+                    path: PathBuf::from(path),
+                    bytes: *bytes,
+                    modified_at: None,
+                    created_at: None,
+                    accessed_at: None,
+                    content_hash: Some(hash.to_string()),
+                    hash_sampled: false,
+                    disk_usage: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let mut conn = create_in_memory_db();
+        let id = insert_snapshot(
+            &mut conn,
+            &snapshot(
+                "/a",
+                1,
+                &[
+                    ("small1", 10, "s"),
+                    ("small2", 10, "s"),
+                    ("big1", 100, "b"),
+                    ("big2", 100, "b"),
+                    ("big3", 100, "b"),
+                    ("unique", 50, "u"),
+                    ("empty1", 0, "e"),
+                    ("empty2", 0, "e"),
+                ],
+            ),
+        )
+        .unwrap();
+
+        let groups = find_duplicates(&conn, &[id]).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            (groups[0].content_hash.as_str(), groups[0].wasted_bytes),
+            ("b", 200)
+        );
+        assert_eq!(groups[0].files.len(), 3);
+        assert_eq!(
+            (groups[1].content_hash.as_str(), groups[1].wasted_bytes),
+            ("s", 10)
+        );
+    }
+
+    #[test]
+    fn test_find_duplicates_across_latest_snapshots() {
+        let mut conn = create_in_memory_db();
+        insert_snapshot(&mut conn, &snapshot("/a", 1, &[("old", 10, "x")])).unwrap();
+        insert_snapshot(&mut conn, &snapshot("/a", 2, &[("file", 10, "h")])).unwrap();
+        insert_snapshot(&mut conn, &snapshot("/b", 1, &[("copy", 10, "h")])).unwrap();
+        insert_snapshot(&mut conn, &snapshot("/c", 1, &[("other", 10, "x")])).unwrap();
+
+        let ids = list_latest_snapshot_ids(&conn).unwrap();
+        assert_eq!(ids.len(), 3);
+
+        let groups = find_duplicates(&conn, &ids).unwrap();
+        assert_eq!(groups.len(), 1);
+        let roots: Vec<_> = groups[0].files.iter().map(|f| f.root.clone()).collect();
+        assert_eq!(roots, vec![PathBuf::from("/a"), PathBuf::from("/b")]);
+    }
+
+    #[test]
+    fn test_hardlinks_are_not_reclaimable() {
+        let mut conn = create_in_memory_db();
+        let mut snapshot = snapshot(
+            "/a",
+            1,
+            &[("file", 100, "h"), ("link", 100, "h"), ("copy", 100, "h")],
+        );
+        for (file, inode) in snapshot.files.iter_mut().zip([1, 1, 2]) {
+            file.disk_usage = Some(DiskUsage {
+                allocated_bytes: 4096,
+                device: 1,
+                inode,
+            });
+        }
+        let id = insert_snapshot(&mut conn, &snapshot).unwrap();
+
+        let groups = find_duplicates(&conn, &[id]).unwrap();
+        assert_eq!(groups[0].files.len(), 3);
+        // Deleting the copy frees its data, deleting the link frees nothing
+        assert_eq!(groups[0].wasted_bytes, 100);
+    }
+
+    #[test]
+    fn test_inodes_of_other_snapshots_are_distinct() {
+        let mut conn = create_in_memory_db();
+        // Roots from different hosts, whose files happen to have the same inode numbers
+        let mut ids = Vec::new();
+        for root in ["/a", "/b"] {
+            let mut snapshot = snapshot(root, 1, &[("file", 100, "h")]);
+            snapshot.files[0].disk_usage = Some(DiskUsage {
+                allocated_bytes: 4096,
+                device: 1,
+                inode: 1,
+            });
+            ids.push(insert_snapshot(&mut conn, &snapshot).unwrap());
+        }
+
+        let groups = find_duplicates(&conn, &ids).unwrap();
+        assert_eq!(groups[0].files.len(), 2);
+        assert_eq!(groups[0].wasted_bytes, 100);
+    }
+}
@@ -0,0 +1,181 @@
+use rusqlite::{Connection, Result};
+
+use crate::models::FileOccurrence;
+use crate::utils::hashing::HashAlgorithm;
+use crate::utils::{from_nanos, path_from_bytes};
+
+/// What to search for across all snapshots
+#[derive(Debug, Clone)]
+pub enum FileFilter {
+    /// Files whose content hash starts with the given prefix
+    HashPrefix(String),
+    /// Files whose name (or relative path) matches the given glob
+    Name(String),
+}
+
+/// Finds the files matching `filter` in every snapshot of every root.
+///
+/// Each distinct root, path and content is reported once, with the first and last snapshot it
+/// was seen in, ordered by root and path.
+pub fn find_files(conn: &Connection, filter: &FileFilter) -> Result<Vec<FileOccurrence>> {
+    let (condition, value) = match filter {
+        // A range instead of LIKE, so that the index on content_hash is used
+        FileFilter::HashPrefix(prefix) => (
+            "f.content_hash >= ?1 AND f.content_hash < ?1 || char(1114111)",
+            prefix.to_lowercase(),
+        ),
+        FileFilter::Name(glob) => (
+            "(CAST(f.path AS TEXT) GLOB ?1 OR CAST(f.path AS TEXT) GLOB '*/' || ?1)",
+            glob.clone(),
+        ),
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT r.path, f.path, f.content_hash, f.bytes, s.id, s.timestamp, s.git_commit_hash,
+            s.hash_algorithm
+        FROM files f
+        JOIN snapshots s ON s.id = f.snapshot_id
+        JOIN roots r ON r.id = s.root_id
+        WHERE {condition}
+        ORDER BY r.path, f.path, s.hash_algorithm, f.content_hash, s.timestamp, s.sequence"
+    ))?;
+    let mut rows = stmt.query([value])?;
+
+    let mut occurrences: Vec<FileOccurrence> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let root = path_from_bytes(row.get(0)?);
+        let path = path_from_bytes(row.get(1)?);
+        let content_hash: Option<String> = row.get(2)?;
+        let hash_algorithm: HashAlgorithm = row.get(7)?;
+        let snapshot_id: i64 = row.get(4)?;
+        let timestamp = from_nanos(row.get(5)?);
+
+        match occurrences.last_mut() {
+            Some(last)
+                if last.root == root
+                    && last.path == path
+                    && last.hash_algorithm == hash_algorithm
+                    && last.content_hash == content_hash =>
+            {
+                last.last_snapshot = snapshot_id;
+                last.last_seen = timestamp;
+            }
+            _ => occurrences.push(FileOccurrence {
+                root,
+                path,
+                hash_algorithm,
+                content_hash,
+                bytes: row.get(3)?,
+                first_snapshot: snapshot_id,
+                first_seen: timestamp,
+                last_snapshot: snapshot_id,
+                last_seen: timestamp,
+                git_commit_hash: row.get(6)?,
+            }),
+        }
+    }
+
+    Ok(occurrences)
+}
+
+/// Returns the algorithms the file contents of any snapshot were hashed with
+pub fn list_hash_algorithms(conn: &Connection) -> Result<Vec<HashAlgorithm>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT hash_algorithm FROM snapshots
+        WHERE hash_mode != 'metadata'
+        ORDER BY hash_algorithm",
+    )?;
+    stmt.query_map([], |row| row.get(0))?.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{initialize_schema, insert_snapshot};
+    use crate::models::{FileMetadata, Snapshot};
+    use crate::utils::hashing::HashMode;
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn snapshot(root: &str, timestamp: u64, files: &[(&str, &str)]) -> Snapshot {
+        Snapshot {
+            root: PathBuf::from(root),
+            timestamp: UNIX_EPOCH + Duration::from_secs(timestamp),
+            git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
+            files: files
+                .iter()
+                .map(|(path, hash)| FileMetadata {
+                    path: PathBuf::from(path),
+                    bytes: 1,
+                    modified_at: None,
+                    created_at: None,
+                    accessed_at: None,
+                    content_hash: Some(hash.to_string()),
+                    hash_sampled: false,
+                    disk_usage: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_find_files() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        let first =
+            insert_snapshot(&mut conn, &snapshot("/a", 1, &[("x/photo.jpg", "abc1")])).unwrap();
+        let second =
+            insert_snapshot(&mut conn, &snapshot("/a", 2, &[("x/photo.jpg", "abc1")])).unwrap();
+        insert_snapshot(&mut conn, &snapshot("/b", 3, &[("photo.jpg", "abd2")])).unwrap();
+        insert_snapshot(&mut conn, &snapshot("/b", 4, &[("notes.txt", "ffff")])).unwrap();
+
+        let found = find_files(&conn, &FileFilter::HashPrefix("ABC".to_string())).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].root, PathBuf::from("/a"));
+        assert_eq!(
+            (found[0].first_snapshot, found[0].last_snapshot),
+            (first, second)
+        );
+
+        let found = find_files(&conn, &FileFilter::Name("*.jpg".to_string())).unwrap();
+        assert_eq!(found.len(), 2);
+
+        let found = find_files(&conn, &FileFilter::Name("photo.jpg".to_string())).unwrap();
+        assert_eq!(found.len(), 2);
+
+        let found = find_files(&conn, &FileFilter::HashPrefix("ab".to_string())).unwrap();
+        assert_eq!(found.len(), 2);
+    }
+
+    #[test]
+    fn test_list_hash_algorithms() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        assert!(list_hash_algorithms(&conn).unwrap().is_empty());
+
+        insert_snapshot(&mut conn, &snapshot("/a", 1, &[("a.txt", "abc1")])).unwrap();
+        let sha256 = Snapshot {
+            hash_algorithm: HashAlgorithm::Sha256,
+            ..snapshot("/a", 2, &[("a.txt", "abc2")])
+        };
+        insert_snapshot(&mut conn, &sha256).unwrap();
+        // Without hashes, the algorithm of a snapshot doesn't matter
+        let unhashed = Snapshot {
+            hash_algorithm: HashAlgorithm::Xxh3,
+            hash_mode: HashMode::Metadata,
+            ..snapshot("/b", 3, &[])
+        };
+        insert_snapshot(&mut conn, &unhashed).unwrap();
+
+        let mut algorithms = list_hash_algorithms(&conn).unwrap();
+        algorithms.sort_by_key(|algorithm| algorithm.to_string());
+        assert_eq!(
+            algorithms,
+            vec![HashAlgorithm::Blake3, HashAlgorithm::Sha256]
+        );
+    }
+}
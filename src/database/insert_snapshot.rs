@@ -1,55 +1,77 @@
+use super::ensure_root;
 use crate::models::Snapshot;
+use crate::utils::{clamp_nanos, path_to_bytes, to_nanos};
 use rusqlite::{Connection, Result, params};
-use std::time::UNIX_EPOCH;
 
 pub fn insert_snapshot(conn: &mut Connection, snapshot: &Snapshot) -> Result<i64> {
     let tx = conn.transaction()?;
 
-    let timestamp = snapshot
-        .timestamp
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
+    let timestamp = clamp_nanos(snapshot.timestamp);
 
-    // Insert Snapshot Row
+    // Insert Snapshot Row, after every snapshot recorded so far in the sequence
+    let root_id = ensure_root(&tx, &snapshot.root)?;
     tx.execute(
-        "INSERT INTO snapshots (root, timestamp, git_commit_hash) VALUES (?1, ?2, ?3)",
+        "INSERT INTO snapshots
+        (root_id, timestamp, git_commit_hash, hash_algorithm, hash_mode, atime_preserved, sequence)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, (SELECT COALESCE(MAX(sequence), 0) + 1 FROM snapshots))",
         params![
-            snapshot.root.to_string_lossy(),
+            root_id,
             timestamp,
-            snapshot.git_commit_hash
+            snapshot.git_commit_hash,
+            snapshot.hash_algorithm,
+            snapshot.hash_mode,
+            snapshot.atime_preserved
         ],
     )?;
 
     let snapshot_id = tx.last_insert_rowid();
 
+    // Insert Scan Statistics
+    if let Some(stats) = &snapshot.scan_stats {
+        tx.execute(
+            "UPDATE snapshots
+            SET scan_duration_ms = ?2, files_visited = ?3, paths_ignored = ?4, bytes_hashed = ?5
+            WHERE id = ?1",
+            params![
+                snapshot_id,
+                stats.duration.as_millis() as i64,
+                stats.files_visited as i64,
+                stats.paths_ignored as i64,
+                stats.bytes_hashed as i64
+            ],
+        )?;
+        for error in &stats.errors {
+            tx.execute(
+                "INSERT INTO scan_errors (snapshot_id, path, error) VALUES (?1, ?2, ?3)",
+                params![snapshot_id, path_to_bytes(&error.path), error.error],
+            )?;
+        }
+    }
+
     // Insert Files
     for file in &snapshot.files {
-        let modified = file
-            .modified_at
-            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-            .map(|d| d.as_secs() as i64);
-        let created = file
-            .created_at
-            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-            .map(|d| d.as_secs() as i64);
-        let accessed = file
-            .accessed_at
-            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-            .map(|d| d.as_secs() as i64);
+        let modified = file.modified_at.and_then(to_nanos);
+        let created = file.created_at.and_then(to_nanos);
+        let accessed = file.accessed_at.and_then(to_nanos);
 
         tx.execute(
             "INSERT INTO files
-            (snapshot_id, path, bytes, modified_at, created_at, accessed_at, content_hash)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            (snapshot_id, path, bytes, modified_at, created_at, accessed_at, content_hash, hash_sampled,
+             allocated_bytes, device, inode)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 snapshot_id,
-                file.path.to_string_lossy(),
+                path_to_bytes(&file.path),
                 file.bytes as i64, // Cast u64 to i64 for SQLite
                 modified,
                 created,
                 accessed,
-                file.content_hash
+                file.content_hash,
+                file.hash_sampled,
+                // Device and inode numbers are stored bit for bit, they may not fit an i64
+                file.disk_usage.map(|usage| usage.allocated_bytes as i64),
+                file.disk_usage.map(|usage| usage.device as i64),
+                file.disk_usage.map(|usage| usage.inode as i64)
             ],
         )?;
     }
@@ -62,8 +84,8 @@ pub fn insert_snapshot(conn: &mut Connection, snapshot: &Snapshot) -> Result<i64
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::database::initialize_schema;
-    use crate::models::FileMetadata;
+    use crate::database::{get_scan_stats, initialize_schema};
+    use crate::models::{FileMetadata, ScanError, ScanStats};
     use rusqlite::{Connection, params};
     use std::path::PathBuf;
     use std::time::SystemTime;
@@ -88,6 +110,8 @@ mod tests {
                     created_at: Some(SystemTime::now()),
                     accessed_at: Some(SystemTime::now()),
                     content_hash: Some("hash1".to_string()),
+                    hash_sampled: false,
+                    disk_usage: None,
                 },
                 FileMetadata {
                     path: PathBuf::from("file2.txt"),
@@ -96,29 +120,31 @@ mod tests {
                     created_at: Some(SystemTime::now()),
                     accessed_at: Some(SystemTime::now()),
                     content_hash: Some("hash2".to_string()),
+                    hash_sampled: false,
+                    disk_usage: None,
                 },
             ],
             git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
         };
 
         let snapshot_id = insert_snapshot(&mut conn, &snapshot).unwrap();
         assert_eq!(snapshot_id, 1);
 
         let mut stmt = conn
-            .prepare("SELECT root, timestamp FROM snapshots WHERE id = ?1")
+            .prepare(
+                "SELECT r.path, s.timestamp FROM snapshots s
+                JOIN roots r ON r.id = s.root_id WHERE s.id = ?1",
+            )
             .unwrap();
         stmt.query_row(params![snapshot_id], |row| {
-            let root: String = row.get(0).unwrap();
+            let root: Vec<u8> = row.get(0).unwrap();
             let timestamp: i64 = row.get(1).unwrap();
-            assert_eq!(root, "/tmp");
-            assert_eq!(
-                timestamp,
-                snapshot
-                    .timestamp
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs() as i64
-            );
+            assert_eq!(root, b"/tmp");
+            assert_eq!(timestamp, clamp_nanos(snapshot.timestamp));
             Ok(())
         })
         .unwrap();
@@ -131,4 +157,59 @@ mod tests {
             .unwrap();
         assert_eq!(count, 2);
     }
+
+    #[test]
+    fn test_insert_timestamp_before_epoch() {
+        let mut conn = create_in_memory_db();
+        let snapshot = Snapshot {
+            root: PathBuf::from("/repo"),
+            timestamp: std::time::UNIX_EPOCH - std::time::Duration::from_secs(60),
+            files: Vec::new(),
+            git_commit_hash: Some("abc".to_string()),
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
+        };
+
+        let snapshot_id = insert_snapshot(&mut conn, &snapshot).unwrap();
+        let timestamp: i64 = conn
+            .query_row(
+                "SELECT timestamp FROM snapshots WHERE id = ?1",
+                [snapshot_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(timestamp, 0);
+    }
+
+    #[test]
+    fn test_insert_scan_stats() {
+        let mut conn = create_in_memory_db();
+        let stats = ScanStats {
+            duration: std::time::Duration::from_millis(1500),
+            files_visited: 10,
+            paths_ignored: 2,
+            bytes_hashed: 4096,
+            errors: vec![ScanError {
+                path: PathBuf::from("secret.txt"),
+                error: "Permission denied".to_string(),
+            }],
+        };
+        let snapshot = Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp: SystemTime::now(),
+            git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: true,
+            scan_stats: Some(stats.clone()),
+            files: vec![],
+        };
+        let snapshot_id = insert_snapshot(&mut conn, &snapshot).unwrap();
+
+        let stored = get_scan_stats(&conn, snapshot_id).unwrap().unwrap();
+        assert_eq!(stored, stats);
+        assert!(!stored.is_complete());
+    }
 }
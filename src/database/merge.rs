@@ -0,0 +1,461 @@
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Result, params};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use super::{find_root_by_name, find_root_by_uuid, find_root_id};
+use crate::utils::{display_path, hashing, path_from_bytes, path_to_bytes};
+
+/// The outcome of merging another database into this one
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Number of snapshots copied into this database
+    pub merged: usize,
+    /// Number of snapshots that were already present and skipped
+    pub skipped: usize,
+}
+
+/// The schema name the other database is attached under
+const OTHER: &str = "other";
+
+/// Copies every snapshot (and its files) from the database at `other_path` into this one.
+///
+/// Snapshots that already exist are skipped, which makes merging repeatable. A snapshot is
+/// considered a duplicate when this database already holds a snapshot of the same root for
+/// the same Git commit or, for filesystem snapshots, one taken at the same time with the same
+/// content fingerprint. Roots are matched by their identity first, then by their path, and keep
+/// their registration (see `chronicle track`): a name is carried over unless it is already used
+/// here. Copied snapshots receive new ids. The merge happens in a single transaction.
+///
+/// The other database is only read. If its schema is older than the current one, a migrated
+/// temporary copy of it is merged instead; a database with a newer schema is refused.
+pub fn merge_database(
+    conn: &mut Connection,
+    other_path: &Path,
+) -> Result<MergeSummary, Box<dyn std::error::Error>> {
+    let other = Connection::open_with_flags(other_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let version: i64 = other.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let current = super::MIGRATIONS.len() as i64;
+    if version > current {
+        return Err(format!(
+            "{} was written by a newer version of chronicle (schema version {version}, expected {current})",
+            other_path.display()
+        )
+        .into());
+    }
+    if version == current {
+        return Ok(attach_and_merge(conn, other_path)?);
+    }
+
+    // The copy lives in a private directory, removed along with it
+    let dir = tempfile::tempdir()?;
+    let copy = dir.path().join("merge.db");
+    log::info!(
+        "Migrating a copy of {} from schema version {version}",
+        other_path.display()
+    );
+    other.execute("VACUUM INTO ?1", [copy.to_string_lossy()])?;
+    drop(other);
+    drop(super::open(&copy)?);
+    Ok(attach_and_merge(conn, &copy)?)
+}
+
+/// Attaches the database at `path` read-only and merges it
+fn attach_and_merge(conn: &mut Connection, path: &Path) -> Result<MergeSummary> {
+    conn.execute(
+        &format!("ATTACH DATABASE ?1 AS {OTHER}"),
+        [read_only_uri(path)],
+    )?;
+    let result = merge_attached(conn);
+    conn.execute(&format!("DETACH DATABASE {OTHER}"), [])?;
+    result
+}
+
+/// An SQLite URI that opens the database at `path` read-only
+fn read_only_uri(path: &Path) -> String {
+    let mut uri = String::from("file:");
+    for &byte in path_to_bytes(path) {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            let _ = write!(uri, "%{byte:02X}");
+        }
+    }
+    uri.push_str("?mode=ro");
+    uri
+}
+
+fn merge_attached(conn: &mut Connection) -> Result<MergeSummary> {
+    let tx = conn.transaction()?;
+    let mut summary = MergeSummary::default();
+
+    // The root of this database each root of the other one corresponds to
+    let mut root_ids = HashMap::new();
+    let other_roots: Vec<(i64, PathBuf, String, Option<String>, bool)> = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT id, path, uuid, name, tracked FROM {OTHER}.roots"
+        ))?;
+        stmt.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                path_from_bytes(row.get(1)?),
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })?
+        .collect::<Result<_>>()?
+    };
+    for (other_root_id, path, uuid, name, tracked) in other_roots {
+        let root_id = match find_root_by_uuid(&tx, &uuid)? {
+            Some((root_id, _)) => root_id,
+            None => match find_root_id(&tx, &path)? {
+                Some(root_id) => root_id,
+                None => {
+                    // Keep the identity of the root, so that its marker file still matches
+                    tx.execute(
+                        "INSERT INTO roots (path, uuid) VALUES (?1, ?2)",
+                        params![path_to_bytes(&path), uuid],
+                    )?;
+                    tx.last_insert_rowid()
+                }
+            },
+        };
+        if tracked {
+            tx.execute("UPDATE roots SET tracked = 1 WHERE id = ?1", [root_id])?;
+        }
+        if let Some(name) = name {
+            match find_root_by_name(&tx, &name)? {
+                None => {
+                    tx.execute(
+                        "UPDATE roots SET name = ?1 WHERE id = ?2 AND name IS NULL",
+                        params![name, root_id],
+                    )?;
+                }
+                Some((id, _)) if id == root_id => {}
+                Some((_, used_by)) => log::warn!(
+                    "The name '{name}' of {} is already used by {}, merging the root without it",
+                    display_path(&path),
+                    display_path(&used_by)
+                ),
+            }
+        }
+        root_ids.insert(other_root_id, root_id);
+    }
+
+    let others: Vec<(i64, i64, PathBuf, i64, Option<String>)> = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT s.id, s.root_id, r.path, s.timestamp, s.git_commit_hash
+            FROM {OTHER}.snapshots s JOIN {OTHER}.roots r ON r.id = s.root_id
+            ORDER BY s.timestamp, s.sequence"
+        ))?;
+        stmt.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                path_from_bytes(row.get(2)?),
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })?
+        .collect::<Result<_>>()?
+    };
+
+    for (other_id, other_root_id, root, timestamp, git_commit_hash) in others {
+        let root_id = root_ids[&other_root_id];
+        if let Some(existing) = find_duplicate(&tx, other_id, root_id, timestamp, &git_commit_hash)?
+        {
+            log::debug!(
+                "Skipping snapshot {other_id} of {}, already present as {existing}",
+                display_path(&root)
+            );
+            summary.skipped += 1;
+            continue;
+        }
+
+        tx.execute(
+            &format!(
+                "INSERT INTO snapshots
+                (root_id, timestamp, git_commit_hash, hash_algorithm, hash_mode, atime_preserved,
+                scan_duration_ms, files_visited, paths_ignored, bytes_hashed, sequence)
+                SELECT ?1, timestamp, git_commit_hash, hash_algorithm, hash_mode, atime_preserved,
+                scan_duration_ms, files_visited, paths_ignored, bytes_hashed,
+                (SELECT COALESCE(MAX(sequence), 0) + 1 FROM main.snapshots)
+                FROM {OTHER}.snapshots WHERE id = ?2"
+            ),
+            params![root_id, other_id],
+        )?;
+        let new_id = tx.last_insert_rowid();
+
+        tx.execute(
+            &format!(
+                "INSERT INTO files
+                (snapshot_id, path, bytes, modified_at, created_at, accessed_at, content_hash, hash_sampled,
+                 allocated_bytes, device, inode)
+                SELECT ?1, path, bytes, modified_at, created_at, accessed_at, content_hash, hash_sampled,
+                 allocated_bytes, device, inode
+                FROM {OTHER}.files WHERE snapshot_id = ?2"
+            ),
+            params![new_id, other_id],
+        )?;
+
+        tx.execute(
+            &format!(
+                "INSERT INTO scan_errors (snapshot_id, path, error)
+                SELECT ?1, path, error FROM {OTHER}.scan_errors WHERE snapshot_id = ?2 ORDER BY id"
+            ),
+            params![new_id, other_id],
+        )?;
+
+        log::debug!(
+            "Merged snapshot {other_id} of {} as {new_id}",
+            display_path(&root)
+        );
+        summary.merged += 1;
+    }
+
+    tx.commit()?;
+    Ok(summary)
+}
+
+/// Finds a snapshot in this database that duplicates the given snapshot of the other database
+fn find_duplicate(
+    conn: &Connection,
+    other_id: i64,
+    root_id: i64,
+    timestamp: i64,
+    git_commit_hash: &Option<String>,
+) -> Result<Option<i64>> {
+    if let Some(hash) = git_commit_hash {
+        return conn
+            .query_row(
+                "SELECT id FROM main.snapshots WHERE root_id = ?1 AND git_commit_hash = ?2 LIMIT 1",
+                params![root_id, hash],
+                |row| row.get(0),
+            )
+            .optional();
+    }
+
+    let candidates: Vec<i64> = {
+        let mut stmt = conn.prepare(
+            "SELECT id FROM main.snapshots
+            WHERE root_id = ?1 AND timestamp = ?2 AND git_commit_hash IS NULL",
+        )?;
+        stmt.query_map(params![root_id, timestamp], |row| row.get(0))?
+            .collect::<Result<_>>()?
+    };
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    let fingerprint = snapshot_fingerprint(conn, OTHER, other_id)?;
+    for candidate in candidates {
+        if snapshot_fingerprint(conn, "main", candidate)? == fingerprint {
+            return Ok(Some(candidate));
+        }
+    }
+    Ok(None)
+}
+
+/// Computes a fingerprint of the content of a snapshot from the path, size and hash of every file
+fn snapshot_fingerprint(conn: &Connection, schema: &str, snapshot_id: i64) -> Result<String> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT path, bytes, content_hash FROM {schema}.files WHERE snapshot_id = ?1 ORDER BY path"
+    ))?;
+    let mut rows = stmt.query([snapshot_id])?;
+
+    let mut content = Vec::new();
+    while let Some(row) = rows.next()? {
+        let path: Vec<u8> = row.get(0)?;
+        let bytes: i64 = row.get(1)?;
+        let content_hash: Option<String> = row.get(2)?;
+        content.extend_from_slice(&path);
+        content.push(0);
+        content.extend_from_slice(bytes.to_string().as_bytes());
+        content.push(0);
+        content.extend_from_slice(content_hash.unwrap_or_default().as_bytes());
+        content.push(b'\n');
+    }
+
+    Ok(hashing::hash_content(&content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{
+        MIGRATIONS, ensure_root, find_root_id, get_files_for_snapshot, get_root_uuid,
+        insert_snapshot, list_roots, list_snapshot_ids, open, track_root,
+    };
+    use crate::models::{FileMetadata, Snapshot};
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn snapshot(root: &str, timestamp: u64, hash: &str, git: Option<&str>) -> Snapshot {
+        Snapshot {
+            root: PathBuf::from(root),
+            timestamp: UNIX_EPOCH + Duration::from_secs(timestamp),
+            git_commit_hash: git.map(str::to_string),
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
+            files: vec![FileMetadata {
+                path: PathBuf::from("file1.txt"),
+                bytes: 123,
+                modified_at: None,
+                created_at: None,
+                accessed_at: None,
+                content_hash: Some(hash.to_string()),
+                hash_sampled: false,
+                disk_usage: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_merge_is_repeatable() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut main = open(&dir.path().join("main.db")).unwrap();
+        let other_path = dir.path().join("other.db");
+        let mut other = open(&other_path).unwrap();
+
+        // Shared snapshot, present in both databases
+        insert_snapshot(&mut main, &snapshot("/a", 1, "h1", None)).unwrap();
+        insert_snapshot(&mut other, &snapshot("/a", 1, "h1", None)).unwrap();
+        // Same time, different content
+        insert_snapshot(&mut other, &snapshot("/a", 1, "h2", None)).unwrap();
+        // Same commit, imported at a different time
+        insert_snapshot(&mut main, &snapshot("/repo", 5, "h3", Some("abc"))).unwrap();
+        insert_snapshot(&mut other, &snapshot("/repo", 6, "h3", Some("abc"))).unwrap();
+        // Only in the other database
+        insert_snapshot(&mut other, &snapshot("/b", 2, "h4", None)).unwrap();
+        drop(other);
+
+        let summary = merge_database(&mut main, &other_path).unwrap();
+        assert_eq!(
+            summary,
+            MergeSummary {
+                merged: 2,
+                skipped: 2
+            }
+        );
+
+        let summary = merge_database(&mut main, &other_path).unwrap();
+        assert_eq!(
+            summary,
+            MergeSummary {
+                merged: 0,
+                skipped: 4
+            }
+        );
+
+        let root_id = find_root_id(&main, Path::new("/b")).unwrap();
+        let ids = list_snapshot_ids(&main, root_id).unwrap();
+        assert_eq!(ids.len(), 1);
+        let files = get_files_for_snapshot(&main, ids[0]).unwrap();
+        assert_eq!(files[0].content_hash.as_deref(), Some("h4"));
+        assert_eq!(list_snapshot_ids(&main, None).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_merge_leaves_older_database_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut main = open(&dir.path().join("main.db")).unwrap();
+        let other_path = dir.path().join("other.db");
+
+        // A database written before the disk usage columns were added
+        let version = MIGRATIONS.len() - 1;
+        let other = Connection::open(&other_path).unwrap();
+        other.execute_batch(include_str!("schema.sql")).unwrap();
+        for migration in &MIGRATIONS[..version] {
+            other.execute_batch(migration).unwrap();
+        }
+        other
+            .pragma_update(None, "user_version", version as i64)
+            .unwrap();
+        other
+            .execute_batch(
+                "INSERT INTO roots (path, uuid) VALUES ('/a', 'u1');
+                INSERT INTO snapshots (root_id, timestamp, sequence) VALUES (1, 1000, 1);
+                INSERT INTO files (snapshot_id, path, bytes) VALUES (1, CAST('f.txt' AS BLOB), 3);",
+            )
+            .unwrap();
+        drop(other);
+        let before = std::fs::read(&other_path).unwrap();
+
+        let summary = merge_database(&mut main, &other_path).unwrap();
+        assert_eq!(summary.merged, 1);
+        assert_eq!(std::fs::read(&other_path).unwrap(), before);
+
+        let root_id = find_root_id(&main, Path::new("/a")).unwrap().unwrap();
+        let ids = list_snapshot_ids(&main, Some(root_id)).unwrap();
+        let files = get_files_for_snapshot(&main, ids[0]).unwrap();
+        assert_eq!(files[0].path, PathBuf::from("f.txt"));
+        assert_eq!(files[0].disk_usage, None);
+    }
+
+    #[test]
+    fn test_merge_matches_roots_by_identity() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut main = open(&dir.path().join("main.db")).unwrap();
+        let other_path = dir.path().join("other.db");
+        let mut other = open(&other_path).unwrap();
+
+        // The same root, mounted elsewhere on the other host
+        let main_root = ensure_root(&main, Path::new("/mnt/photos")).unwrap();
+        insert_snapshot(&mut other, &snapshot("/media/photos", 1, "h1", None)).unwrap();
+        other
+            .execute(
+                "UPDATE roots SET uuid = ?1 WHERE path = CAST('/media/photos' AS BLOB)",
+                [get_root_uuid(&main, main_root).unwrap()],
+            )
+            .unwrap();
+        // A root only known to the other database keeps its identity
+        insert_snapshot(&mut other, &snapshot("/b", 2, "h2", None)).unwrap();
+        let other_b = find_root_id(&other, Path::new("/b")).unwrap().unwrap();
+        let uuid_b = get_root_uuid(&other, other_b).unwrap();
+        drop(other);
+
+        merge_database(&mut main, &other_path).unwrap();
+
+        assert_eq!(
+            find_root_id(&main, Path::new("/media/photos")).unwrap(),
+            None
+        );
+        assert_eq!(list_snapshot_ids(&main, Some(main_root)).unwrap().len(), 1);
+        let main_b = find_root_id(&main, Path::new("/b")).unwrap().unwrap();
+        assert_eq!(get_root_uuid(&main, main_b).unwrap(), uuid_b);
+    }
+
+    #[test]
+    fn test_merge_keeps_registrations() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut main = open(&dir.path().join("main.db")).unwrap();
+        let other_path = dir.path().join("other.db");
+        let other = open(&other_path).unwrap();
+
+        let taken = ensure_root(&main, Path::new("/mnt/docs")).unwrap();
+        track_root(&main, taken, Some("docs")).unwrap();
+        for (path, name) in [("/a", "photos"), ("/b", "docs"), ("/c", "music")] {
+            let id = ensure_root(&other, Path::new(path)).unwrap();
+            track_root(&other, id, Some(name)).unwrap();
+        }
+        // Known here under the same path, but not registered yet
+        ensure_root(&main, Path::new("/c")).unwrap();
+        drop(other);
+
+        merge_database(&mut main, &other_path).unwrap();
+
+        let roots = list_roots(&main).unwrap();
+        let registration = |path: &str| {
+            let root = roots.iter().find(|r| r.path == Path::new(path)).unwrap();
+            (root.name.clone(), root.tracked)
+        };
+        assert_eq!(registration("/a"), (Some("photos".to_string()), true));
+        // The name is already used by another root here
+        assert_eq!(registration("/b"), (None, true));
+        assert_eq!(registration("/c"), (Some("music".to_string()), true));
+        assert_eq!(registration("/mnt/docs"), (Some("docs".to_string()), true));
+    }
+}
@@ -0,0 +1,29 @@
+-- ROOTS
+-- Every tracked root gets a persistent identity, so that its history survives moves and remounts
+CREATE TABLE roots (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    uuid TEXT NOT NULL UNIQUE
+);
+
+INSERT INTO roots (path, uuid)
+SELECT root, lower(hex(randomblob(16))) FROM (SELECT DISTINCT root FROM snapshots);
+
+-- SNAPSHOTS
+-- Reference the root by id instead of by its path
+CREATE TABLE snapshots_new (
+    id INTEGER PRIMARY KEY,
+    root_id INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    git_commit_hash TEXT,
+    FOREIGN KEY(root_id) REFERENCES roots(id)
+);
+
+INSERT INTO snapshots_new (id, root_id, timestamp, git_commit_hash)
+SELECT s.id, r.id, s.timestamp, s.git_commit_hash
+FROM snapshots s JOIN roots r ON r.path = s.root;
+
+DROP TABLE snapshots;
+ALTER TABLE snapshots_new RENAME TO snapshots;
+
+CREATE INDEX idx_snapshots_root ON snapshots(root_id);
@@ -0,0 +1,6 @@
+-- ROOTS
+-- Roots can be registered explicitly (tracked) and given a friendly name
+ALTER TABLE roots ADD COLUMN name TEXT;
+ALTER TABLE roots ADD COLUMN tracked INTEGER NOT NULL DEFAULT 0;
+
+CREATE UNIQUE INDEX idx_roots_name ON roots(name);
@@ -0,0 +1,42 @@
+-- Deleting a root deletes its snapshots, and deleting a snapshot deletes its files
+
+-- Files without a snapshot can't be reached anymore
+DELETE FROM files WHERE snapshot_id NOT IN (SELECT id FROM snapshots);
+
+-- SNAPSHOTS
+CREATE TABLE snapshots_new (
+    id INTEGER PRIMARY KEY,
+    root_id INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    git_commit_hash TEXT,
+    FOREIGN KEY(root_id) REFERENCES roots(id) ON DELETE CASCADE
+);
+
+INSERT INTO snapshots_new (id, root_id, timestamp, git_commit_hash)
+SELECT id, root_id, timestamp, git_commit_hash FROM snapshots;
+
+DROP TABLE snapshots;
+ALTER TABLE snapshots_new RENAME TO snapshots;
+
+CREATE INDEX idx_snapshots_root ON snapshots(root_id);
+
+-- FILES
+CREATE TABLE files_new (
+    id INTEGER PRIMARY KEY,
+    snapshot_id INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    bytes INTEGER NOT NULL,
+    modified_at INTEGER,
+    created_at INTEGER,
+    accessed_at INTEGER,
+    content_hash TEXT,
+    FOREIGN KEY(snapshot_id) REFERENCES snapshots(id) ON DELETE CASCADE
+);
+
+INSERT INTO files_new (id, snapshot_id, path, bytes, modified_at, created_at, accessed_at, content_hash)
+SELECT id, snapshot_id, path, bytes, modified_at, created_at, accessed_at, content_hash FROM files;
+
+DROP TABLE files;
+ALTER TABLE files_new RENAME TO files;
+
+CREATE INDEX idx_files_snapshot ON files(snapshot_id);
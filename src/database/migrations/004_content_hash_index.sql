@@ -0,0 +1,2 @@
+-- Look up files by content hash, e.g. to find where a file came from
+CREATE INDEX idx_files_content_hash ON files(content_hash);
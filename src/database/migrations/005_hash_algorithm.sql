@@ -0,0 +1,3 @@
+-- The algorithm the content hashes of a snapshot were computed with.
+-- Earlier snapshots were always hashed with BLAKE3.
+ALTER TABLE snapshots ADD COLUMN hash_algorithm TEXT NOT NULL DEFAULT 'blake3';
@@ -0,0 +1,3 @@
+-- Whether the files of a snapshot were hashed ('full') or only stat'ed ('metadata').
+-- Earlier snapshots were always fully hashed.
+ALTER TABLE snapshots ADD COLUMN hash_mode TEXT NOT NULL DEFAULT 'full';
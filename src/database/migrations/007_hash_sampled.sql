@@ -0,0 +1,2 @@
+-- Whether the content hash of a file was computed from samples of its content only.
+ALTER TABLE files ADD COLUMN hash_sampled INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Whether taking a snapshot left the access times of the files untouched.
+-- Earlier snapshots read every file without preserving access times.
+ALTER TABLE snapshots ADD COLUMN atime_preserved INTEGER NOT NULL DEFAULT 0;
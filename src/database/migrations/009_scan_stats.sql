@@ -0,0 +1,17 @@
+-- Statistics about the scan a snapshot was taken with.
+-- They are NULL for Git imports and for snapshots taken before they were recorded.
+ALTER TABLE snapshots ADD COLUMN scan_duration_ms INTEGER;
+ALTER TABLE snapshots ADD COLUMN files_visited INTEGER;
+ALTER TABLE snapshots ADD COLUMN paths_ignored INTEGER;
+ALTER TABLE snapshots ADD COLUMN bytes_hashed INTEGER;
+
+-- SCAN ERRORS: the paths that could not be read while scanning
+CREATE TABLE scan_errors (
+    id INTEGER PRIMARY KEY,
+    snapshot_id INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    error TEXT NOT NULL,
+    FOREIGN KEY(snapshot_id) REFERENCES snapshots(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_scan_errors_snapshot ON scan_errors(snapshot_id);
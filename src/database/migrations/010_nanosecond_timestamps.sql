@@ -0,0 +1,12 @@
+-- Times were stored as whole seconds since the Unix epoch; they are now nanoseconds.
+UPDATE snapshots SET timestamp = timestamp * 1000000000;
+UPDATE files SET
+    modified_at = modified_at * 1000000000,
+    created_at = created_at * 1000000000,
+    accessed_at = accessed_at * 1000000000;
+
+-- Snapshots with the same timestamp are ordered by when they were recorded
+ALTER TABLE snapshots ADD COLUMN sequence INTEGER NOT NULL DEFAULT 0;
+UPDATE snapshots SET sequence = id;
+
+CREATE INDEX idx_snapshots_order ON snapshots(root_id, timestamp, sequence);
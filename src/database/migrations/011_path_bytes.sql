@@ -0,0 +1,43 @@
+-- Paths are stored as their raw bytes, so that names that aren't valid UTF-8 survive
+
+-- FILES
+CREATE TABLE files_new (
+    id INTEGER PRIMARY KEY,
+    snapshot_id INTEGER NOT NULL,
+    path BLOB NOT NULL,
+    bytes INTEGER NOT NULL,
+    modified_at INTEGER,
+    created_at INTEGER,
+    accessed_at INTEGER,
+    content_hash TEXT,
+    hash_sampled INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY(snapshot_id) REFERENCES snapshots(id) ON DELETE CASCADE
+);
+
+INSERT INTO files_new
+    (id, snapshot_id, path, bytes, modified_at, created_at, accessed_at, content_hash, hash_sampled)
+SELECT id, snapshot_id, CAST(path AS BLOB), bytes, modified_at, created_at, accessed_at, content_hash, hash_sampled
+FROM files;
+
+DROP TABLE files;
+ALTER TABLE files_new RENAME TO files;
+
+CREATE INDEX idx_files_snapshot ON files(snapshot_id);
+CREATE INDEX idx_files_content_hash ON files(content_hash);
+
+-- SCAN ERRORS
+CREATE TABLE scan_errors_new (
+    id INTEGER PRIMARY KEY,
+    snapshot_id INTEGER NOT NULL,
+    path BLOB NOT NULL,
+    error TEXT NOT NULL,
+    FOREIGN KEY(snapshot_id) REFERENCES snapshots(id) ON DELETE CASCADE
+);
+
+INSERT INTO scan_errors_new (id, snapshot_id, path, error)
+SELECT id, snapshot_id, CAST(path AS BLOB), error FROM scan_errors;
+
+DROP TABLE scan_errors;
+ALTER TABLE scan_errors_new RENAME TO scan_errors;
+
+CREATE INDEX idx_scan_errors_snapshot ON scan_errors(snapshot_id);
@@ -0,0 +1,5 @@
+-- Space each file takes on disk, and its identity to count hardlinks once.
+-- Unknown (NULL) for files recorded before, and for those not scanned from a Unix filesystem.
+ALTER TABLE files ADD COLUMN allocated_bytes INTEGER;
+ALTER TABLE files ADD COLUMN device INTEGER;
+ALTER TABLE files ADD COLUMN inode INTEGER;
@@ -0,0 +1,18 @@
+-- Root paths are stored as their raw bytes too, like the paths of files
+
+-- ROOTS
+CREATE TABLE roots_new (
+    id INTEGER PRIMARY KEY,
+    path BLOB NOT NULL UNIQUE,
+    uuid TEXT NOT NULL UNIQUE,
+    name TEXT,
+    tracked INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO roots_new (id, path, uuid, name, tracked)
+SELECT id, CAST(path AS BLOB), uuid, name, tracked FROM roots;
+
+DROP TABLE roots;
+ALTER TABLE roots_new RENAME TO roots;
+
+CREATE UNIQUE INDEX idx_roots_name ON roots(name);
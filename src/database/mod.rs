@@ -1,11 +1,25 @@
+mod check;
 mod compute_diff;
+mod delete;
+mod duplicates;
+mod find;
 mod insert_snapshot;
+mod merge;
 mod query;
+mod roots;
+mod stats;
 
+pub use check::*;
 pub use compute_diff::*;
+pub use delete::*;
+pub use duplicates::*;
+pub use find::*;
 pub use insert_snapshot::*;
+pub use merge::*;
 pub use query::*;
+pub use roots::*;
 use rusqlite::{Connection, Result};
+pub use stats::*;
 use std::path::Path;
 
 use crate::{models, utils}; // Added these imports
@@ -13,46 +27,113 @@ use crate::{models, utils}; // Added these imports
 /// Opens (or Creates) the Chronicle database at the given path
 pub fn open(path: &Path) -> Result<Connection> {
     let mut conn = Connection::open(path)?;
+    // Wait for other chronicle processes (or threads) to finish writing
+    conn.busy_timeout(std::time::Duration::from_secs(60))?;
+    // Deletes cascade through the foreign keys, so they must be enforced
+    conn.pragma_update(None, "foreign_keys", true)?;
     initialize_schema(&mut conn)?;
     Ok(conn)
 }
 
-// Create tables if they don't exist
+/// Schema migrations, applied in order on top of `schema.sql`.
+/// The database's `user_version` records how many of them have been applied.
+const MIGRATIONS: &[&str] = &[
+    include_str!("migrations/001_root_identity.sql"),
+    include_str!("migrations/002_root_registry.sql"),
+    include_str!("migrations/003_cascade_deletes.sql"),
+    include_str!("migrations/004_content_hash_index.sql"),
+    include_str!("migrations/005_hash_algorithm.sql"),
+    include_str!("migrations/006_hash_mode.sql"),
+    include_str!("migrations/007_hash_sampled.sql"),
+    include_str!("migrations/008_atime_preserved.sql"),
+    include_str!("migrations/009_scan_stats.sql"),
+    include_str!("migrations/010_nanosecond_timestamps.sql"),
+    include_str!("migrations/011_path_bytes.sql"),
+    include_str!("migrations/012_disk_usage.sql"),
+    include_str!("migrations/013_root_path_bytes.sql"),
+];
+
+// Create tables if they don't exist and bring them up to date
 pub fn initialize_schema(conn: &mut Connection) -> Result<()> {
-    conn.execute_batch(include_str!("schema.sql"))?;
-    Ok(())
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version == 0 {
+        conn.execute_batch(include_str!("schema.sql"))?;
+    }
+    migrate(conn, version as usize)
+}
+
+/// Applies the migrations after `version`, each in its own transaction.
+///
+/// Foreign keys are switched off while migrating, so that tables can be rebuilt, and
+/// are checked for violations before each migration is committed.
+fn migrate(conn: &mut Connection, version: usize) -> Result<()> {
+    if version >= MIGRATIONS.len() {
+        return Ok(());
+    }
+
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let result = (|| {
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            let violations: i64 =
+                tx.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| {
+                    row.get(0)
+                })?;
+            if violations > 0 {
+                return Err(rusqlite::Error::SqliteFailure(
+                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT_FOREIGNKEY),
+                    Some(format!("Migration {} violates foreign keys", i + 1)),
+                ));
+            }
+            tx.pragma_update(None, "user_version", (i + 1) as i64)?;
+            tx.commit()?;
+            log::debug!("Applied database migration {}", i + 1);
+        }
+        Ok(())
+    })();
+    conn.pragma_update(None, "foreign_keys", true)?;
+    result
 }
 
+/// Stores the snapshot if anything changed since the last snapshot of the same root, or if it
+/// fully hashes files the last snapshot only sampled.
+///
+/// Returns the computed diff along with the id of the stored snapshot, which is
+/// `None` when no changes were detected and nothing was written.
 pub fn store_snapshot(
     snapshot: models::Snapshot,
     db_path_override: Option<&std::path::PathBuf>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    normalization: utils::PathNormalization,
+) -> Result<(models::Diff, Option<i64>), Box<dyn std::error::Error>> {
     let db_path = utils::get_chronicle_db_path(db_path_override)?;
     let mut conn = open(&db_path)?;
 
     // Compute Diff
-    let diff = compute_diff(&mut conn, &snapshot.root.to_string_lossy(), &snapshot.files)?;
+    let diff = compute_diff(
+        &conn,
+        find_root_id(&conn, &snapshot.root)?,
+        &snapshot.files,
+        snapshot
+            .scan_stats
+            .as_ref()
+            .map_or(&[][..], |stats| &stats.errors),
+        snapshot.hash_algorithm,
+        snapshot.hash_mode,
+        normalization,
+    )?;
+    // Also store an unchanged snapshot if it replaces sampled hashes with full ones
     if diff.is_empty() {
-        println!("No changes detected");
-        return Ok(());
-    }
-
-    // Print Diff
-    if diff.added.is_empty() && diff.removed.is_empty() && diff.modified.is_empty() {
-        println!("No changes detected");
-        return Ok(());
-    }
-
-    // Print summary
-    println!("Snapshot detected changes:");
-    if !diff.added.is_empty() {
-        println!("  + {} added files", diff.added.len());
-    }
-    if !diff.removed.is_empty() {
-        println!("  - {} removed files", diff.removed.len());
-    }
-    if !diff.modified.is_empty() {
-        println!("  * {} modified files", diff.modified.len());
+        let upgrades = count_hash_upgrades(
+            &conn,
+            &snapshot.root,
+            &snapshot.files,
+            snapshot.hash_algorithm,
+        )?;
+        if upgrades == 0 {
+            return Ok((diff, None));
+        }
+        log::info!("Upgrading {upgrades} sampled hashes to full hashes");
     }
 
     debug_assert!(
@@ -62,7 +143,11 @@ pub fn store_snapshot(
 
     // Insert Snapshot
     let snapshot_id = insert_snapshot(&mut conn, &snapshot)?;
-    println!("Snapshot stored with id {snapshot_id}");
+    log::debug!(
+        "Stored snapshot {snapshot_id} for {} ({} files)",
+        snapshot.root.display(),
+        snapshot.files.len()
+    );
 
-    Ok(())
+    Ok((diff, Some(snapshot_id)))
 }
@@ -1,27 +1,28 @@
-use rusqlite::{Connection, OptionalExtension, Result, Row, params};
+use rusqlite::ToSql;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, Value, ValueRef};
+use rusqlite::{Connection, OptionalExtension, Result, Row, params, params_from_iter};
 
-pub fn snapshot_exists(conn: &Connection, root: &str, git_commit_hash: &str) -> Result<bool> {
+pub fn snapshot_exists(conn: &Connection, root_id: i64, git_commit_hash: &str) -> Result<bool> {
     let count: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM snapshots WHERE root = ?1 AND git_commit_hash = ?2",
-        params![root, git_commit_hash],
+        "SELECT COUNT(*) FROM snapshots WHERE root_id = ?1 AND git_commit_hash = ?2",
+        params![root_id, git_commit_hash],
         |row| row.get(0),
     )?;
     Ok(count > 0)
 }
 
-pub fn get_penultimate_snapshot_id(conn: &Connection, root: &str) -> Result<Option<i64>> {
+pub fn get_penultimate_snapshot_id(conn: &Connection, root_id: i64) -> Result<Option<i64>> {
     conn.query_row(
-        "SELECT id FROM snapshots WHERE root = ?1 ORDER BY timestamp DESC LIMIT 1 OFFSET 1",
-        [root],
+        "SELECT id FROM snapshots WHERE root_id = ?1 ORDER BY timestamp DESC, sequence DESC LIMIT 1 OFFSET 1",
+        [root_id],
         |row| row.get(0),
     )
     .optional()
 }
 
-use std::path::PathBuf;
-use std::time::UNIX_EPOCH;
-
-use crate::models::{FileMetadata, SnapshotMetadata};
+use crate::models::{DiskUsage, FileMetadata, ScanError, ScanStats, Snapshot, SnapshotMetadata};
+use crate::utils::hashing::{HashAlgorithm, HashMode};
+use crate::utils::{from_nanos, path_from_bytes};
 
 pub fn get_files_for_snapshot(conn: &Connection, snapshot_id: i64) -> Result<Vec<FileMetadata>> {
     let mut stmt = conn.prepare(
@@ -31,7 +32,11 @@ pub fn get_files_for_snapshot(conn: &Connection, snapshot_id: i64) -> Result<Vec
             modified_at,
             created_at,
             accessed_at,
-            content_hash
+            content_hash,
+            hash_sampled,
+            allocated_bytes,
+            device,
+            inode
         FROM
             files
         WHERE
@@ -46,6 +51,39 @@ pub fn get_files_for_snapshot(conn: &Connection, snapshot_id: i64) -> Result<Vec
     Ok(files)
 }
 
+/// Returns the files of a snapshot matching an SQL condition over `files` (aliased `f`), sorted by path.
+///
+/// The condition's parameters are bound to anonymous `?` placeholders, in order.
+pub fn query_files(
+    conn: &Connection,
+    snapshot_id: i64,
+    condition: &str,
+    params: &[Value],
+) -> Result<Vec<FileMetadata>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT
+            f.path,
+            f.bytes,
+            f.modified_at,
+            f.created_at,
+            f.accessed_at,
+            f.content_hash,
+            f.hash_sampled,
+            f.allocated_bytes,
+            f.device,
+            f.inode
+        FROM
+            files f
+        WHERE
+            f.snapshot_id = ? AND ({condition})
+        ORDER BY
+            f.path"
+    ))?;
+    let params = std::iter::once(Value::Integer(snapshot_id)).chain(params.iter().cloned());
+    stmt.query_map(params_from_iter(params), |row| FileMetadata::try_from(row))?
+        .collect()
+}
+
 impl TryFrom<&Row<'_>> for FileMetadata {
     type Error = rusqlite::Error;
 
@@ -53,47 +91,94 @@ impl TryFrom<&Row<'_>> for FileMetadata {
         let modified_at: Option<i64> = row.get(2)?;
         let created_at: Option<i64> = row.get(3)?;
         let accessed_at: Option<i64> = row.get(4)?;
+        let allocated_bytes: Option<i64> = row.get(7)?;
+        let device: Option<i64> = row.get(8)?;
+        let inode: Option<i64> = row.get(9)?;
 
         Ok(FileMetadata {
-            path: PathBuf::from(row.get::<_, String>(0)?),
+            path: path_from_bytes(row.get(0)?),
             bytes: row.get::<_, i64>(1)? as u64,
-            modified_at: modified_at.map(|t| UNIX_EPOCH + std::time::Duration::from_secs(t as u64)),
-            created_at: created_at.map(|t| UNIX_EPOCH + std::time::Duration::from_secs(t as u64)),
-            accessed_at: accessed_at.map(|t| UNIX_EPOCH + std::time::Duration::from_secs(t as u64)),
+            modified_at: modified_at.map(from_nanos),
+            created_at: created_at.map(from_nanos),
+            accessed_at: accessed_at.map(from_nanos),
             content_hash: row.get(5)?,
+            hash_sampled: row.get(6)?,
+            disk_usage: match (allocated_bytes, device, inode) {
+                (Some(allocated_bytes), Some(device), Some(inode)) => Some(DiskUsage {
+                    allocated_bytes: allocated_bytes as u64,
+                    device: device as u64,
+                    inode: inode as u64,
+                }),
+                _ => None,
+            },
         })
     }
 }
 
-pub fn get_latest_snapshot_id(conn: &Connection, root: &str) -> Result<Option<i64>> {
+pub fn get_latest_snapshot_id(conn: &Connection, root_id: i64) -> Result<Option<i64>> {
     conn.query_row(
-        "SELECT id FROM snapshots WHERE root = ?1 ORDER BY timestamp DESC LIMIT 1",
-        [root],
+        "SELECT id FROM snapshots WHERE root_id = ?1 ORDER BY timestamp DESC, sequence DESC LIMIT 1",
+        [root_id],
         |row| row.get(0),
     )
     .optional()
 }
 
-pub fn list_snapshots_for_root(conn: &Connection, root: &str) -> Result<Vec<SnapshotMetadata>> {
+/// Returns the id of the latest snapshot of every root
+pub fn list_latest_snapshot_ids(conn: &Connection) -> Result<Vec<i64>> {
     let mut stmt = conn.prepare(
+        "SELECT
+            (SELECT s.id FROM snapshots s WHERE s.root_id = r.id ORDER BY s.timestamp DESC, s.sequence DESC LIMIT 1)
+        FROM
+            roots r
+        ORDER BY
+            r.id",
+    )?;
+    let ids = stmt
+        .query_map([], |row| row.get::<_, Option<i64>>(0))?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(ids.into_iter().flatten().collect())
+}
+
+/// SQL for the disk usage of the files of the snapshot `snapshot_id` (an SQL expression): the
+/// bytes allocated to them, counting hardlinks to the same inode once, like `du`. NULL if it
+/// wasn't recorded for any of them.
+pub(crate) fn disk_usage_sql(snapshot_id: &str) -> String {
+    format!(
+        "(SELECT SUM(allocated_bytes) FROM (
+            SELECT MAX(allocated_bytes) AS allocated_bytes
+            FROM files
+            WHERE snapshot_id = {snapshot_id} AND allocated_bytes IS NOT NULL
+            GROUP BY device, inode
+        ))"
+    )
+}
+
+pub fn list_snapshots_for_root(conn: &Connection, root_id: i64) -> Result<Vec<SnapshotMetadata>> {
+    let mut stmt = conn.prepare(&format!(
         "SELECT
             s.id,
-            s.root,
+            r.path,
             s.timestamp,
             COUNT(f.id),
-            SUM(f.bytes)
+            COALESCE(SUM(f.bytes), 0),
+            s.atime_preserved,
+            {}
         FROM
             snapshots s
         JOIN
+            roots r ON r.id = s.root_id
+        LEFT JOIN
             files f ON s.id = f.snapshot_id
         WHERE
-            s.root = ?1
+            s.root_id = ?1
         GROUP BY
             s.id
         ORDER BY
-            s.timestamp DESC",
-    )?;
-    let snapshot_iter = stmt.query_map([root], |row| SnapshotMetadata::try_from(row))?;
+            s.timestamp DESC, s.sequence DESC",
+        disk_usage_sql("s.id")
+    ))?;
+    let snapshot_iter = stmt.query_map([root_id], |row| SnapshotMetadata::try_from(row))?;
 
     let mut snapshots = Vec::new();
     for snapshot_metadata in snapshot_iter {
@@ -106,15 +191,323 @@ impl TryFrom<&Row<'_>> for SnapshotMetadata {
     type Error = rusqlite::Error;
 
     fn try_from(row: &Row) -> Result<Self> {
-        let timestamp_secs: i64 = row.get(2)?;
-        let timestamp = UNIX_EPOCH + std::time::Duration::from_secs(timestamp_secs as u64);
+        let timestamp = from_nanos(row.get(2)?);
 
         Ok(SnapshotMetadata {
             id: row.get(0)?,
-            root: PathBuf::from(row.get::<_, String>(1)?),
+            root: path_from_bytes(row.get(1)?),
             timestamp,
             file_count: row.get(3)?,
             total_size: row.get(4)?,
+            atime_preserved: row.get(5)?,
+            disk_usage: row.get(6)?,
+            scan_stats: None,
         })
     }
 }
+
+/// Resolves a revision string to a snapshot id for the given root.
+///
+/// A revision can be a snapshot ID, `HEAD` (the latest snapshot) or `HEAD~1`
+/// (the snapshot before the latest). Returns `None` if no such snapshot exists.
+pub fn resolve_revision(conn: &Connection, root_id: i64, rev: &str) -> Result<Option<i64>> {
+    if rev.eq_ignore_ascii_case("HEAD") {
+        get_latest_snapshot_id(conn, root_id)
+    } else if rev.eq_ignore_ascii_case("HEAD~1") {
+        get_penultimate_snapshot_id(conn, root_id)
+    } else {
+        match rev.parse::<i64>() {
+            Ok(id) => conn
+                .query_row(
+                    "SELECT id FROM snapshots WHERE id = ?1 AND root_id = ?2",
+                    params![id, root_id],
+                    |row| row.get(0),
+                )
+                .optional(),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// Resolves a point in time (nanoseconds since the Unix epoch) to the last snapshot of the root
+/// taken at or before it
+pub fn resolve_timestamp(conn: &Connection, root_id: i64, timestamp: i64) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM snapshots
+        WHERE root_id = ?1 AND timestamp <= ?2
+        ORDER BY timestamp DESC, sequence DESC LIMIT 1",
+        params![root_id, timestamp],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Returns the ids of all snapshots, oldest first, optionally restricted to a single root
+pub fn list_snapshot_ids(conn: &Connection, root_id: Option<i64>) -> Result<Vec<i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM snapshots WHERE ?1 IS NULL OR root_id = ?1 ORDER BY timestamp ASC, sequence ASC",
+    )?;
+    let ids = stmt
+        .query_map([root_id], |row| row.get(0))?
+        .collect::<Result<Vec<i64>>>()?;
+    Ok(ids)
+}
+
+/// Loads a complete snapshot, including its files
+pub fn get_snapshot(conn: &Connection, snapshot_id: i64) -> Result<Option<Snapshot>> {
+    let row = conn
+        .query_row(
+            "SELECT r.path, s.timestamp, s.git_commit_hash, s.hash_algorithm, s.hash_mode,
+                s.atime_preserved
+            FROM snapshots s JOIN roots r ON r.id = s.root_id
+            WHERE s.id = ?1",
+            [snapshot_id],
+            |row| {
+                Ok((
+                    path_from_bytes(row.get(0)?),
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, HashAlgorithm>(3)?,
+                    row.get::<_, HashMode>(4)?,
+                    row.get::<_, bool>(5)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    let Some((root, timestamp, git_commit_hash, hash_algorithm, hash_mode, atime_preserved)) = row
+    else {
+        return Ok(None);
+    };
+
+    let mut files = get_files_for_snapshot(conn, snapshot_id)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(Some(Snapshot {
+        root,
+        timestamp: from_nanos(timestamp),
+        git_commit_hash,
+        hash_algorithm,
+        hash_mode,
+        atime_preserved,
+        scan_stats: get_scan_stats(conn, snapshot_id)?,
+        files,
+    }))
+}
+
+/// Returns the statistics of the scan a snapshot was taken with, including the paths that
+/// could not be read. `None` for Git imports and snapshots taken before they were recorded.
+pub fn get_scan_stats(conn: &Connection, snapshot_id: i64) -> Result<Option<ScanStats>> {
+    let row = conn
+        .query_row(
+            "SELECT scan_duration_ms, files_visited, paths_ignored, bytes_hashed
+            FROM snapshots WHERE id = ?1 AND scan_duration_ms IS NOT NULL",
+            [snapshot_id],
+            |row| {
+                Ok(ScanStats {
+                    duration: std::time::Duration::from_millis(row.get::<_, i64>(0)? as u64),
+                    files_visited: row.get::<_, i64>(1)? as u64,
+                    paths_ignored: row.get::<_, i64>(2)? as u64,
+                    bytes_hashed: row.get::<_, i64>(3)? as u64,
+                    errors: Vec::new(),
+                })
+            },
+        )
+        .optional()?;
+    let Some(mut stats) = row else {
+        return Ok(None);
+    };
+
+    let mut stmt =
+        conn.prepare("SELECT path, error FROM scan_errors WHERE snapshot_id = ?1 ORDER BY id")?;
+    stats.errors = stmt
+        .query_map([snapshot_id], |row| {
+            Ok(ScanError {
+                path: path_from_bytes(row.get(0)?),
+                error: row.get(1)?,
+            })
+        })?
+        .collect::<Result<_>>()?;
+    Ok(Some(stats))
+}
+
+/// Returns how the files of a snapshot were hashed: the algorithm, and whether they were hashed at all
+pub fn get_snapshot_hashing(
+    conn: &Connection,
+    snapshot_id: i64,
+) -> Result<Option<(HashAlgorithm, HashMode)>> {
+    conn.query_row(
+        "SELECT hash_algorithm, hash_mode FROM snapshots WHERE id = ?1",
+        [snapshot_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+}
+
+impl ToSql for HashAlgorithm {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for HashAlgorithm {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+impl ToSql for HashMode {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for HashMode {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// Finds a snapshot of the root taken at the given time (in nanoseconds) for the same Git commit
+pub fn find_snapshot(
+    conn: &Connection,
+    root_id: i64,
+    timestamp: i64,
+    git_commit_hash: Option<&str>,
+) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM snapshots
+        WHERE root_id = ?1 AND timestamp = ?2 AND git_commit_hash IS ?3
+        LIMIT 1",
+        params![root_id, timestamp, git_commit_hash],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{find_root_id, initialize_schema, insert_snapshot};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const SECOND: i64 = 1_000_000_000;
+
+    fn snapshot(timestamp: std::time::SystemTime) -> Snapshot {
+        Snapshot {
+            root: PathBuf::from("/tmp"),
+            timestamp,
+            git_commit_hash: None,
+            hash_algorithm: Default::default(),
+            hash_mode: Default::default(),
+            atime_preserved: false,
+            scan_stats: None,
+            files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_timestamp() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        let mut ids = Vec::new();
+        for timestamp in [100, 200] {
+            let snapshot = snapshot(UNIX_EPOCH + Duration::from_secs(timestamp));
+            ids.push(insert_snapshot(&mut conn, &snapshot).unwrap());
+        }
+        let root_id = find_root_id(&conn, Path::new("/tmp")).unwrap().unwrap();
+
+        assert_eq!(
+            resolve_timestamp(&conn, root_id, 99 * SECOND).unwrap(),
+            None
+        );
+        assert_eq!(
+            resolve_timestamp(&conn, root_id, 100 * SECOND).unwrap(),
+            Some(ids[0])
+        );
+        assert_eq!(
+            resolve_timestamp(&conn, root_id, 199 * SECOND).unwrap(),
+            Some(ids[0])
+        );
+        assert_eq!(
+            resolve_timestamp(&conn, root_id, 500 * SECOND).unwrap(),
+            Some(ids[1])
+        );
+    }
+
+    #[test]
+    fn test_snapshot_order() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        let time = UNIX_EPOCH + Duration::new(100, 500);
+        let earlier = insert_snapshot(&mut conn, &snapshot(time)).unwrap();
+        let tied = insert_snapshot(&mut conn, &snapshot(time)).unwrap();
+        // Inserted last, but taken a nanosecond before the others
+        let older = insert_snapshot(&mut conn, &snapshot(time - Duration::from_nanos(1))).unwrap();
+        let root_id = find_root_id(&conn, Path::new("/tmp")).unwrap().unwrap();
+
+        assert_eq!(get_latest_snapshot_id(&conn, root_id).unwrap(), Some(tied));
+        assert_eq!(
+            get_penultimate_snapshot_id(&conn, root_id).unwrap(),
+            Some(earlier)
+        );
+        assert_eq!(
+            list_snapshot_ids(&conn, Some(root_id)).unwrap(),
+            vec![older, earlier, tied]
+        );
+        assert_eq!(get_snapshot(&conn, tied).unwrap().unwrap().timestamp, time);
+    }
+
+    #[test]
+    fn test_disk_usage() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        let file = |path: &str, bytes, disk_usage| FileMetadata {
+            path: PathBuf::from(path),
+            bytes,
+            modified_at: None,
+            created_at: None,
+            accessed_at: None,
+            content_hash: None,
+            hash_sampled: false,
+            disk_usage,
+        };
+        let usage = |allocated_bytes, inode| DiskUsage {
+            allocated_bytes,
+            device: u64::MAX,
+            inode,
+        };
+
+        let mut old = snapshot(UNIX_EPOCH + Duration::from_secs(100));
+        old.files = vec![file("a", 5000, None)];
+        insert_snapshot(&mut conn, &old).unwrap();
+
+        let mut new = snapshot(UNIX_EPOCH + Duration::from_secs(200));
+        new.files = vec![
+            file("a", 5000, Some(usage(8192, 1))),
+            // A hardlink to the same data
+            file("b", 5000, Some(usage(8192, 1))),
+            // Sparse, nothing allocated
+            file("sparse", 1 << 20, Some(usage(0, 2))),
+        ];
+        let id = insert_snapshot(&mut conn, &new).unwrap();
+        let root_id = find_root_id(&conn, Path::new("/tmp")).unwrap().unwrap();
+
+        let files = get_files_for_snapshot(&conn, id).unwrap();
+        let a = files.iter().find(|f| f.path == Path::new("a")).unwrap();
+        assert_eq!(a.disk_usage, Some(usage(8192, 1)));
+
+        let snapshots = list_snapshots_for_root(&conn, root_id).unwrap();
+        assert_eq!(snapshots[0].total_size, 10000 + (1 << 20));
+        assert_eq!(snapshots[0].disk_usage, Some(8192));
+        assert_eq!(snapshots[1].total_size, 5000);
+        assert_eq!(snapshots[1].disk_usage, None);
+    }
+}
@@ -0,0 +1,287 @@
+use rusqlite::{Connection, OptionalExtension, Result, params};
+use std::path::{Path, PathBuf};
+
+use super::query::disk_usage_sql;
+use crate::models::{RootSource, RootSummary};
+use crate::utils::{from_nanos, path_from_bytes, path_to_bytes};
+
+/// Returns the id of the root tracked at the given path
+pub fn find_root_id(conn: &Connection, path: &Path) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM roots WHERE path = ?1",
+        [path_to_bytes(path)],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Returns the id and current path of the root with the given persistent identity
+pub fn find_root_by_uuid(conn: &Connection, uuid: &str) -> Result<Option<(i64, PathBuf)>> {
+    conn.query_row(
+        "SELECT id, path FROM roots WHERE uuid = ?1",
+        [uuid],
+        |row| Ok((row.get(0)?, path_from_bytes(row.get(1)?))),
+    )
+    .optional()
+}
+
+/// Returns the persistent identity of a root
+pub fn get_root_uuid(conn: &Connection, root_id: i64) -> Result<String> {
+    conn.query_row("SELECT uuid FROM roots WHERE id = ?1", [root_id], |row| {
+        row.get(0)
+    })
+}
+
+/// Returns the id of the root tracked at the given path, registering it with a new identity if needed
+pub fn ensure_root(conn: &Connection, path: &Path) -> Result<i64> {
+    if let Some(id) = find_root_id(conn, path)? {
+        return Ok(id);
+    }
+    conn.execute(
+        "INSERT INTO roots (path, uuid) VALUES (?1, lower(hex(randomblob(16))))",
+        [path_to_bytes(path)],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Points an existing root at a new path. All of its snapshots follow along.
+pub fn set_root_path(conn: &Connection, root_id: i64, path: &Path) -> Result<()> {
+    conn.execute(
+        "UPDATE roots SET path = ?1 WHERE id = ?2",
+        params![path_to_bytes(path), root_id],
+    )?;
+    Ok(())
+}
+
+/// Returns the id and path of the root with the given friendly name
+pub fn find_root_by_name(conn: &Connection, name: &str) -> Result<Option<(i64, PathBuf)>> {
+    conn.query_row(
+        "SELECT id, path FROM roots WHERE name = ?1",
+        [name],
+        |row| Ok((row.get(0)?, path_from_bytes(row.get(1)?))),
+    )
+    .optional()
+}
+
+/// Registers the root explicitly, optionally giving it a friendly name
+pub fn track_root(conn: &Connection, root_id: i64, name: Option<&str>) -> Result<()> {
+    conn.execute(
+        "UPDATE roots SET tracked = 1, name = COALESCE(?1, name) WHERE id = ?2",
+        params![name, root_id],
+    )?;
+    Ok(())
+}
+
+/// Unregisters the root and drops its name. Roots without any snapshots are removed entirely.
+pub fn untrack_root(conn: &Connection, root_id: i64) -> Result<()> {
+    conn.execute(
+        "UPDATE roots SET tracked = 0, name = NULL WHERE id = ?1",
+        [root_id],
+    )?;
+    conn.execute(
+        "DELETE FROM roots
+        WHERE id = ?1 AND NOT EXISTS (SELECT 1 FROM snapshots WHERE root_id = ?1)",
+        [root_id],
+    )?;
+    Ok(())
+}
+
+/// Lists every known root with a summary of its history, ordered by path
+pub fn list_roots(conn: &Connection) -> Result<Vec<RootSummary>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT
+            r.id,
+            r.path,
+            r.name,
+            r.tracked,
+            MAX(s.git_commit_hash IS NOT NULL),
+            COUNT(s.id),
+            MIN(s.timestamp),
+            MAX(s.timestamp),
+            (SELECT COUNT(*) FROM files WHERE snapshot_id = latest.id),
+            (SELECT COALESCE(SUM(bytes), 0) FROM files WHERE snapshot_id = latest.id),
+            {}
+        FROM
+            roots r
+        LEFT JOIN
+            snapshots s ON s.root_id = r.id
+        LEFT JOIN
+            snapshots latest ON latest.id = (
+                SELECT id FROM snapshots WHERE root_id = r.id ORDER BY timestamp DESC, sequence DESC LIMIT 1
+            )
+        GROUP BY
+            r.id
+        ORDER BY
+            r.path",
+        disk_usage_sql("latest.id")
+    ))?;
+    let roots = stmt
+        .query_map([], |row| {
+            let to_time = |nanos: Option<i64>| nanos.map(from_nanos);
+            let is_git: Option<bool> = row.get(4)?;
+            Ok(RootSummary {
+                id: row.get(0)?,
+                path: path_from_bytes(row.get(1)?),
+                name: row.get(2)?,
+                tracked: row.get(3)?,
+                source: if is_git.unwrap_or(false) {
+                    RootSource::Git
+                } else {
+                    RootSource::Filesystem
+                },
+                snapshot_count: row.get(5)?,
+                first_snapshot: to_time(row.get(6)?),
+                last_snapshot: to_time(row.get(7)?),
+                file_count: row.get(8)?,
+                total_size: row.get(9)?,
+                disk_usage: row.get(10)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::initialize_schema;
+
+    fn create_in_memory_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_ensure_root_is_stable() {
+        let conn = create_in_memory_db();
+        let id = ensure_root(&conn, Path::new("/data")).unwrap();
+        assert_eq!(ensure_root(&conn, Path::new("/data")).unwrap(), id);
+        assert_ne!(ensure_root(&conn, Path::new("/other")).unwrap(), id);
+
+        let uuid = get_root_uuid(&conn, id).unwrap();
+        assert_eq!(uuid.len(), 32);
+        assert_eq!(
+            find_root_by_uuid(&conn, &uuid).unwrap(),
+            Some((id, PathBuf::from("/data")))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_root() {
+        let conn = create_in_memory_db();
+        let path = path_from_bytes(b"/mnt/bad\xff".to_vec());
+        let id = ensure_root(&conn, &path).unwrap();
+        assert_eq!(find_root_id(&conn, &path).unwrap(), Some(id));
+        // Not confused with the lossy decoding of the name
+        assert_eq!(
+            find_root_id(&conn, Path::new("/mnt/bad\u{fffd}")).unwrap(),
+            None
+        );
+
+        let uuid = get_root_uuid(&conn, id).unwrap();
+        assert_eq!(find_root_by_uuid(&conn, &uuid).unwrap(), Some((id, path)));
+    }
+
+    #[test]
+    fn test_set_root_path() {
+        let conn = create_in_memory_db();
+        let id = ensure_root(&conn, Path::new("/mnt/usb")).unwrap();
+        set_root_path(&conn, id, Path::new("/media/usb")).unwrap();
+
+        assert_eq!(find_root_id(&conn, Path::new("/mnt/usb")).unwrap(), None);
+        assert_eq!(
+            find_root_id(&conn, Path::new("/media/usb")).unwrap(),
+            Some(id)
+        );
+    }
+
+    #[test]
+    fn test_track_and_untrack() {
+        let conn = create_in_memory_db();
+        let id = ensure_root(&conn, Path::new("/photos")).unwrap();
+        track_root(&conn, id, Some("photos")).unwrap();
+        assert_eq!(
+            find_root_by_name(&conn, "photos").unwrap(),
+            Some((id, PathBuf::from("/photos")))
+        );
+
+        // Names are unique
+        let other = ensure_root(&conn, Path::new("/other")).unwrap();
+        assert!(track_root(&conn, other, Some("photos")).is_err());
+
+        // Untracking a root without snapshots forgets it entirely
+        untrack_root(&conn, id).unwrap();
+        assert_eq!(find_root_by_name(&conn, "photos").unwrap(), None);
+        assert_eq!(find_root_id(&conn, Path::new("/photos")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_list_roots() {
+        use crate::database::insert_snapshot;
+        use crate::models::{FileMetadata, Snapshot};
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut conn = create_in_memory_db();
+        let file = |hash: &str, bytes| FileMetadata {
+            path: PathBuf::from(hash),
+            bytes,
+            modified_at: None,
+            created_at: None,
+            accessed_at: None,
+            content_hash: Some(hash.to_string()),
+            hash_sampled: false,
+            disk_usage: None,
+        };
+        for (timestamp, files, git) in [
+            (10, vec![file("a", 1)], None),
+            (20, vec![file("a", 1), file("b", 2)], None),
+            (30, vec![file("c", 3)], Some("abc".to_string())),
+        ] {
+            let root = if git.is_some() { "/repo" } else { "/data" };
+            let snapshot = Snapshot {
+                root: PathBuf::from(root),
+                timestamp: UNIX_EPOCH + Duration::from_secs(timestamp),
+                git_commit_hash: git,
+                hash_algorithm: Default::default(),
+                hash_mode: Default::default(),
+                atime_preserved: false,
+                scan_stats: None,
+                files,
+            };
+            insert_snapshot(&mut conn, &snapshot).unwrap();
+        }
+        let tracked = ensure_root(&conn, Path::new("/empty")).unwrap();
+        track_root(&conn, tracked, Some("empty")).unwrap();
+
+        let roots = list_roots(&conn).unwrap();
+        assert_eq!(roots.len(), 3);
+
+        let data = &roots[0];
+        assert_eq!(data.path, PathBuf::from("/data"));
+        assert_eq!(data.source, RootSource::Filesystem);
+        assert_eq!(data.snapshot_count, 2);
+        assert_eq!(
+            data.first_snapshot,
+            Some(UNIX_EPOCH + Duration::from_secs(10))
+        );
+        assert_eq!(
+            data.last_snapshot,
+            Some(UNIX_EPOCH + Duration::from_secs(20))
+        );
+        assert_eq!(data.file_count, 2);
+        assert_eq!(data.total_size, 3);
+        assert_eq!(data.disk_usage, None);
+
+        let empty = &roots[1];
+        assert_eq!(empty.name.as_deref(), Some("empty"));
+        assert!(empty.tracked);
+        assert_eq!(empty.snapshot_count, 0);
+        assert_eq!(empty.last_snapshot, None);
+
+        let repo = &roots[2];
+        assert_eq!(repo.source, RootSource::Git);
+        assert_eq!(repo.file_count, 1);
+    }
+}
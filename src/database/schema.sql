@@ -1,3 +1,5 @@
+-- The initial schema. Later changes are applied on top of it by the migrations.
+
 -- SNAPSHOTS
 CREATE TABLE IF NOT EXISTS snapshots (
     id INTEGER PRIMARY KEY,
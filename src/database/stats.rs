@@ -0,0 +1,128 @@
+use rusqlite::{Connection, Result};
+use std::path::PathBuf;
+
+use crate::utils::path_from_bytes;
+
+/// Storage statistics of the database
+#[derive(Debug, serde::Serialize)]
+pub struct DatabaseStats {
+    /// Size of the database in bytes (page count times page size)
+    pub size_bytes: i64,
+    /// Bytes held by free pages, which `VACUUM` would reclaim
+    pub free_bytes: i64,
+    pub roots: i64,
+    pub snapshots: i64,
+    pub files: i64,
+    pub per_root: Vec<RootStorage>,
+}
+
+/// Storage used by the history of a single root
+#[derive(Debug, serde::Serialize)]
+pub struct RootStorage {
+    #[serde(serialize_with = "crate::utils::serialize_path")]
+    pub path: PathBuf,
+    pub snapshots: i64,
+    pub files: i64,
+    /// Estimated bytes taken by the root's rows, from the size of their values
+    pub estimated_bytes: i64,
+}
+
+/// Approximate per-row overhead of a `files` row besides its text values (integers and record header)
+const FILE_ROW_OVERHEAD: i64 = 48;
+
+/// Collects row counts, the size of the database and the storage used per root
+pub fn database_stats(conn: &Connection) -> Result<DatabaseStats> {
+    let pragma = |name: &str| -> Result<i64> {
+        conn.query_row(&format!("PRAGMA {name}"), [], |row| row.get(0))
+    };
+    let count = |table: &str| -> Result<i64> {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+            row.get(0)
+        })
+    };
+
+    let page_size = pragma("page_size")?;
+
+    let mut stmt = conn.prepare(
+        "SELECT
+            r.path,
+            (SELECT COUNT(*) FROM snapshots s WHERE s.root_id = r.id),
+            COUNT(f.id),
+            COALESCE(SUM(LENGTH(f.path) + COALESCE(LENGTH(f.content_hash), 0)), 0)
+                + COUNT(f.id) * ?1
+        FROM
+            roots r
+        LEFT JOIN
+            snapshots s ON s.root_id = r.id
+        LEFT JOIN
+            files f ON f.snapshot_id = s.id
+        GROUP BY
+            r.id
+        ORDER BY
+            4 DESC",
+    )?;
+    let per_root = stmt
+        .query_map([FILE_ROW_OVERHEAD], |row| {
+            Ok(RootStorage {
+                path: path_from_bytes(row.get(0)?),
+                snapshots: row.get(1)?,
+                files: row.get(2)?,
+                estimated_bytes: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(DatabaseStats {
+        size_bytes: pragma("page_count")? * page_size,
+        free_bytes: pragma("freelist_count")? * page_size,
+        roots: count("roots")?,
+        snapshots: count("snapshots")?,
+        files: count("files")?,
+        per_root,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{initialize_schema, insert_snapshot};
+    use crate::models::{FileMetadata, Snapshot};
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_database_stats() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&mut conn).unwrap();
+        for root in ["/small", "/big", "/big"] {
+            let snapshot = Snapshot {
+                root: PathBuf::from(root),
+                timestamp: SystemTime::now(),
+                git_commit_hash: None,
+                hash_algorithm: Default::default(),
+                hash_mode: Default::default(),
+                atime_preserved: false,
+                scan_stats: None,
+                files: vec![FileMetadata {
+                    path: PathBuf::from("file.txt"),
+                    bytes: 1,
+                    modified_at: None,
+                    created_at: None,
+                    accessed_at: None,
+                    content_hash: Some("hash".to_string()),
+                    hash_sampled: false,
+                    disk_usage: None,
+                }],
+            };
+            insert_snapshot(&mut conn, &snapshot).unwrap();
+        }
+
+        let stats = database_stats(&conn).unwrap();
+        assert_eq!((stats.roots, stats.snapshots, stats.files), (2, 3, 3));
+        assert!(stats.size_bytes > 0);
+
+        let big = &stats.per_root[0];
+        assert_eq!(big.path, PathBuf::from("/big"));
+        assert_eq!((big.snapshots, big.files), (2, 2));
+        assert_eq!(big.estimated_bytes, 2 * (8 + 4 + FILE_ROW_OVERHEAD));
+    }
+}
@@ -1,4 +1,5 @@
 mod cli;
+mod config;
 mod core;
 mod database;
 mod models;
@@ -10,9 +11,16 @@ fn main() {
     // Parse the command line arguments
     let args = cli::args::parse();
 
+    // Setup logging. Diagnostics go to stderr, data output stays on stdout
+    utils::logger::init(
+        utils::logger::level_from_verbosity(args.quiet, args.verbose),
+        args.log_format,
+    );
+    utils::progress::init(args.progress, args.quiet);
+
     // Run the command-line-interface and handle errors
     if let Err(e) = run(&args) {
-        eprintln!("{e}");
+        log::error!("{e}");
         std::process::exit(1);
     }
 }
@@ -22,8 +30,23 @@ fn run(cli: &cli::args::Args) -> Result<(), Box<dyn std::error::Error>> {
     match &cli.command {
         cli::commands::Command::Snapshot(cmd) => cmd.execute(cli),
         cli::commands::Command::List(cmd) => cmd.execute(cli),
+        cli::commands::Command::Ls(cmd) => cmd.execute(cli),
         cli::commands::Command::Status(cmd) => cmd.execute(cli),
         cli::commands::Command::Diff(cmd) => cmd.execute(cli),
+        cli::commands::Command::Dupes(cmd) => cmd.execute(cli),
+        cli::commands::Command::Collisions(cmd) => cmd.execute(cli),
+        cli::commands::Command::Find(cmd) => cmd.execute(cli),
+        cli::commands::Command::Query(cmd) => cmd.execute(cli),
         cli::commands::Command::Git(cmd) => cmd.execute(cli),
+        cli::commands::Command::Export(cmd) => cmd.execute(cli),
+        cli::commands::Command::Import(cmd) => cmd.execute(cli),
+        cli::commands::Command::Db(cmd) => cmd.execute(cli),
+        cli::commands::Command::Root(cmd) => cmd.execute(cli),
+        cli::commands::Command::Roots(cmd) => cmd.execute(cli),
+        cli::commands::Command::Track(cmd) => cmd.execute(cli),
+        cli::commands::Command::Untrack(cmd) => cmd.execute(cli),
+        cli::commands::Command::Rm(cmd) => cmd.execute(cli),
+        cli::commands::Command::Undo(cmd) => cmd.execute(cli),
+        cli::commands::Command::Verify(cmd) => cmd.execute(cli),
     }
 }
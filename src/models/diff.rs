@@ -1,12 +1,43 @@
+use std::path::PathBuf;
+
+use crate::models::ScanError;
+use crate::utils::serialize_paths;
+
 #[derive(serde::Serialize)]
 pub struct Diff {
-    pub added: Vec<String>,
-    pub removed: Vec<String>,
-    pub modified: Vec<String>,
+    #[serde(serialize_with = "serialize_paths")]
+    pub added: Vec<PathBuf>,
+    #[serde(serialize_with = "serialize_paths")]
+    pub removed: Vec<PathBuf>,
+    #[serde(serialize_with = "serialize_paths")]
+    pub modified: Vec<PathBuf>,
+    /// Files that could not be read on one side, so that whether they changed is unknown
+    #[serde(serialize_with = "serialize_paths")]
+    pub unreadable: Vec<PathBuf>,
 }
 
 impl Diff {
+    /// Whether no change is known. Unreadable files don't count as changes.
     pub fn is_empty(&self) -> bool {
         self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
     }
+
+    /// Reports the files missing on one side because they could not be read there as unreadable,
+    /// rather than added or removed. `before` and `after` are the paths each side couldn't read;
+    /// an unreadable directory covers every file below it.
+    pub fn account_for_errors(&mut self, before: &[ScanError], after: &[ScanError]) {
+        let covered = |errors: &[ScanError], path: &PathBuf| {
+            errors.iter().any(|error| path.starts_with(&error.path))
+        };
+        let (unreadable, added): (Vec<_>, Vec<_>) = std::mem::take(&mut self.added)
+            .into_iter()
+            .partition(|path| covered(before, path));
+        self.added = added;
+        self.unreadable.extend(unreadable);
+        let (unreadable, removed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.removed)
+            .into_iter()
+            .partition(|path| covered(after, path));
+        self.removed = removed;
+        self.unreadable.extend(unreadable);
+    }
 }
@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+use crate::utils::hashing::HashAlgorithm;
+use crate::utils::serialize_path;
+
+/// A copy of a duplicated file
+#[derive(Debug, serde::Serialize)]
+pub struct DuplicateFile {
+    #[serde(serialize_with = "serialize_path")]
+    pub root: PathBuf,
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+}
+
+/// A group of files sharing the same content
+#[derive(Debug, serde::Serialize)]
+pub struct DuplicateGroup {
+    pub hash_algorithm: HashAlgorithm,
+    pub content_hash: String,
+    /// Size of each copy
+    pub bytes: i64,
+    /// Bytes that would be reclaimed by keeping a single copy
+    pub wasted_bytes: i64,
+    pub files: Vec<DuplicateFile>,
+}
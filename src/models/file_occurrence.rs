@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::utils::hashing::HashAlgorithm;
+use crate::utils::serialize_path;
+
+/// A file (identified by root, path and content) as recorded across snapshots
+#[derive(Debug, serde::Serialize)]
+pub struct FileOccurrence {
+    #[serde(serialize_with = "serialize_path")]
+    pub root: PathBuf,
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    pub hash_algorithm: HashAlgorithm,
+    pub content_hash: Option<String>,
+    pub bytes: i64,
+    /// The first snapshot the file was seen in
+    pub first_snapshot: i64,
+    pub first_seen: SystemTime,
+    /// The last snapshot the file was seen in
+    pub last_snapshot: i64,
+    pub last_seen: SystemTime,
+    /// The Git commit of the first snapshot, for roots synchronized from Git
+    pub git_commit_hash: Option<String>,
+}
@@ -1,6 +1,7 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct FileMetadata {
     /// The path to the file
+    #[serde(serialize_with = "crate::utils::serialize_path")]
     pub path: std::path::PathBuf,
     /// The size of the file in bytes
     pub bytes: u64,
@@ -12,4 +13,102 @@ pub struct FileMetadata {
     pub accessed_at: Option<std::time::SystemTime>,
     /// The hash of the file content
     pub content_hash: Option<String>,
+    /// Whether `content_hash` was computed from samples of a large file rather than its whole content
+    pub hash_sampled: bool,
+    /// Where the file is stored on disk, unknown for files that weren't scanned from a Unix
+    /// filesystem (e.g. imported from Git history)
+    pub disk_usage: Option<DiskUsage>,
+}
+
+/// The space a file takes on disk, as opposed to its apparent size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DiskUsage {
+    /// Bytes allocated on disk, less than the size for sparse files
+    pub allocated_bytes: u64,
+    /// The device the file is stored on
+    pub device: u64,
+    /// The inode of the file; files with the same device and inode are hardlinks to the same data
+    pub inode: u64,
+}
+
+impl DiskUsage {
+    /// Reads the disk usage of a file from its metadata
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Option<DiskUsage> {
+        use std::os::unix::fs::MetadataExt;
+        Some(DiskUsage {
+            // `st_blocks` is always counted in 512-byte units
+            allocated_bytes: metadata.blocks() * 512,
+            device: metadata.dev(),
+            inode: metadata.ino(),
+        })
+    }
+
+    /// Reads the disk usage of a file from its metadata. Only known on Unix.
+    #[cfg(not(unix))]
+    pub fn from_metadata(_metadata: &std::fs::Metadata) -> Option<DiskUsage> {
+        None
+    }
+}
+
+/// How a file compares to the same path in another snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Unchanged,
+    Modified,
+    /// The file could not be read on one of the sides, and its metadata didn't change
+    Unknown,
+}
+
+impl FileMetadata {
+    /// Compares this file to `other`, the same path in another snapshot.
+    ///
+    /// Compares the content hashes if `compare_hashes` is set, i.e. if both sides were hashed
+    /// with the same algorithm. A missing hash then means the file could not be read: unless
+    /// the metadata proves a change, the outcome is unknown. Sampled hashes only count as proof
+    /// of a change: when they are equal, or when only one of the hashes is sampled, the metadata
+    /// is compared as well. Without `compare_hashes` (e.g. when a snapshot was taken without
+    /// hashing), only the size and the modification time are compared (see [`same_time`]).
+    pub fn compare(&self, other: &FileMetadata, compare_hashes: bool) -> Change {
+        let metadata_changed =
+            self.bytes != other.bytes || !same_time(self.modified_at, other.modified_at);
+
+        if compare_hashes {
+            match (&self.content_hash, &other.content_hash) {
+                (Some(hash), Some(other_hash)) if self.hash_sampled == other.hash_sampled => {
+                    if hash != other_hash {
+                        return Change::Modified;
+                    }
+                    if !self.hash_sampled {
+                        return Change::Unchanged;
+                    }
+                }
+                (Some(_), Some(_)) => {}
+                _ if !metadata_changed => return Change::Unknown,
+                _ => {}
+            }
+        }
+        if metadata_changed {
+            Change::Modified
+        } else {
+            Change::Unchanged
+        }
+    }
+}
+
+/// Whether two file times are the same.
+///
+/// Times are compared to the nanosecond, unless one of them is a whole second: times recorded
+/// before nanoseconds were stored, and those of filesystems with coarse timestamps, are then
+/// compared at the precision they have.
+pub fn same_time(a: Option<std::time::SystemTime>, b: Option<std::time::SystemTime>) -> bool {
+    let since_epoch = |time: Option<std::time::SystemTime>| {
+        time.and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+    };
+    match (since_epoch(a), since_epoch(b)) {
+        (Some(a), Some(b)) if a.subsec_nanos() == 0 || b.subsec_nanos() == 0 => {
+            a.as_secs() == b.as_secs()
+        }
+        (a, b) => a == b,
+    }
 }
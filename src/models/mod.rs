@@ -7,8 +7,26 @@ pub use snapshot::Snapshot;
 mod snapshot_metadata;
 pub use snapshot_metadata::SnapshotMetadata;
 
+mod root_summary;
+pub use root_summary::{RootSource, RootSummary};
+
+mod duplicate_group;
+pub use duplicate_group::{DuplicateFile, DuplicateGroup};
+
+mod file_occurrence;
+pub use file_occurrence::FileOccurrence;
+
+mod scan_stats;
+pub use scan_stats::{ScanError, ScanStats};
+
 mod diff;
 pub use diff::Diff;
 
+mod verify;
+pub use verify::{IssueKind, Severity, VerifyIssue, VerifyReport};
+
 mod table;
 pub use table::Table;
+
+mod name_collision;
+pub use name_collision::{CollisionKind, NameCollision};
@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use crate::utils::serialize_paths;
+
+/// Why names collide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionKind {
+    /// The names only differ in their Unicode normalization form
+    Normalization,
+    /// The names differ in case
+    Case,
+}
+
+impl std::fmt::Display for CollisionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollisionKind::Normalization => write!(f, "normalization"),
+            CollisionKind::Case => write!(f, "case"),
+        }
+    }
+}
+
+/// Names of the same directory that are distinct byte for byte, but collide under Unicode
+/// normalization or case folding
+#[derive(Debug, serde::Serialize)]
+pub struct NameCollision {
+    pub kind: CollisionKind,
+    #[serde(serialize_with = "serialize_paths")]
+    pub paths: Vec<PathBuf>,
+}
@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Where the snapshots of a root come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RootSource {
+    /// Scans of the directory on the filesystem
+    Filesystem,
+    /// Commits imported from a Git repository
+    Git,
+}
+
+impl std::fmt::Display for RootSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RootSource::Filesystem => write!(f, "filesystem"),
+            RootSource::Git => write!(f, "git"),
+        }
+    }
+}
+
+/// Represents a tracked root along with a summary of its history
+#[derive(Debug, serde::Serialize)]
+pub struct RootSummary {
+    pub id: i64,
+    #[serde(serialize_with = "crate::utils::serialize_path")]
+    pub path: PathBuf,
+    /// The friendly name given with `chronicle track --name`
+    pub name: Option<String>,
+    /// Whether the root was registered explicitly with `chronicle track`
+    pub tracked: bool,
+    pub source: RootSource,
+    pub snapshot_count: i64,
+    pub first_snapshot: Option<SystemTime>,
+    pub last_snapshot: Option<SystemTime>,
+    /// Number of files in the latest snapshot
+    pub file_count: i64,
+    /// Total size of the files in the latest snapshot
+    pub total_size: i64,
+    /// Disk usage of the files in the latest snapshot, counting hardlinks once
+    pub disk_usage: Option<i64>,
+}
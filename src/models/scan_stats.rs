@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::utils::{deserialize_path, serialize_path};
+
+/// A path that could not be read while scanning
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ScanError {
+    #[serde(
+        serialize_with = "serialize_path",
+        deserialize_with = "deserialize_path"
+    )]
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Statistics about the scan a snapshot was taken with
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScanStats {
+    /// How long the scan took
+    pub duration: Duration,
+    /// Number of files found
+    pub files_visited: u64,
+    /// Number of paths skipped by ignore rules (an ignored directory counts once)
+    pub paths_ignored: u64,
+    /// Number of bytes read to hash the files
+    pub bytes_hashed: u64,
+    /// Paths that could not be read, and why
+    pub errors: Vec<ScanError>,
+}
+
+impl ScanStats {
+    /// Whether every path could be read
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// The achieved hashing throughput, in bytes per second
+    pub fn read_rate(&self) -> u64 {
+        (self.bytes_hashed as f64 / self.duration.as_secs_f64().max(f64::EPSILON)) as u64
+    }
+}
@@ -1,4 +1,5 @@
-use super::FileMetadata;
+use super::{FileMetadata, ScanStats};
+use crate::utils::hashing::{HashAlgorithm, HashMode};
 
 /// Represents a single snapshot of a directory at a point of time
 #[derive(Debug)]
@@ -9,6 +10,15 @@ pub struct Snapshot {
     pub timestamp: std::time::SystemTime,
     /// The Git commit hash this snapshot corresponds to (if applicable)
     pub git_commit_hash: Option<String>,
+    /// The algorithm the content hashes of the files were computed with
+    pub hash_algorithm: HashAlgorithm,
+    /// Whether the contents of the files were hashed
+    pub hash_mode: HashMode,
+    /// Whether taking the snapshot left the access times of the files untouched, so that
+    /// the access times recorded by the next snapshot reflect actual use
+    pub atime_preserved: bool,
+    /// Statistics about the scan the snapshot was taken with, if it was taken from the filesystem
+    pub scan_stats: Option<ScanStats>,
     /// Files discovered in the snapshot
     pub files: Vec<FileMetadata>,
 }
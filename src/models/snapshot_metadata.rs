@@ -1,12 +1,24 @@
 use std::path::PathBuf;
 use std::time::SystemTime;
 
+use super::ScanStats;
+
 /// Represents the metadata of a snapshot, without the file list
 #[derive(Debug, serde::Serialize)]
 pub struct SnapshotMetadata {
     pub id: i64,
+    #[serde(serialize_with = "crate::utils::serialize_path")]
     pub root: PathBuf,
     pub timestamp: SystemTime,
     pub file_count: i64,
+    /// Apparent size of the files, like `du --apparent-size`
     pub total_size: i64,
+    /// Bytes allocated on disk to the files, counting hardlinks once, like `du`.
+    /// Unknown for snapshots recorded without it.
+    pub disk_usage: Option<i64>,
+    /// Whether taking the snapshot left the access times of the files untouched
+    pub atime_preserved: bool,
+    /// Statistics about the scan, only loaded on demand
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_stats: Option<ScanStats>,
 }
@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use crate::utils::serialize_path;
+
+/// How serious a finding of `verify` is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Expected changes, e.g. a file that was deleted since the snapshot
+    Info,
+    /// The file could not be checked, e.g. because it is unreadable
+    Warning,
+    /// The content changed while size and modification time did not: likely bit rot
+    Critical,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+/// The kind of problem found for a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueKind {
+    /// Content hash changed, size and modification time are identical
+    Corrupted,
+    /// The file exists but its content could not be read
+    Unreadable,
+    /// The file is in the snapshot but no longer on disk
+    Missing,
+}
+
+/// A problem found for a single file while verifying it against a snapshot
+#[derive(Debug, serde::Serialize)]
+pub struct VerifyIssue {
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    pub kind: IssueKind,
+    pub severity: Severity,
+}
+
+/// The outcome of verifying the live tree against a snapshot
+#[derive(Debug, Default, serde::Serialize)]
+pub struct VerifyReport {
+    /// Files whose content matches the snapshot
+    pub verified: usize,
+    /// Files that changed legitimately (size or modification time differ too)
+    pub modified: usize,
+    /// Files that are not in the snapshot
+    pub added: usize,
+    /// Files that could not be compared because the snapshot holds no hash for them
+    pub skipped: usize,
+    pub issues: Vec<VerifyIssue>,
+}
+
+impl VerifyReport {
+    /// The most serious severity among the issues, if there are any
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.issues.iter().map(|issue| issue.severity).max()
+    }
+}
@@ -32,7 +32,7 @@ pub fn get_chronicle_dir() -> std::io::Result<PathBuf> {
 
     #[cfg(not(target_os = "windows"))]
     let base_dir = dirs_next::data_dir()
-        .unwrap_or_else(|| dirs_next::home_dir().unwrap().join(".local/share"))?;
+        .unwrap_or_else(|| dirs_next::home_dir().unwrap().join(".local/share"));
 
     // Chronicle Specific Folder
     let chronicle_dir = base_dir.join(app_name);
@@ -43,6 +43,16 @@ pub fn get_chronicle_dir() -> std::io::Result<PathBuf> {
     Ok(chronicle_dir)
 }
 
+pub fn get_chronicle_config_path(
+    config_path_override: Option<&PathBuf>,
+) -> std::io::Result<PathBuf> {
+    if let Some(path) = config_path_override {
+        return Ok(path.clone());
+    }
+    let chronicle_dir = get_chronicle_dir()?;
+    Ok(chronicle_dir.join("config.toml"))
+}
+
 pub fn get_chronicle_db_path(db_path_override: Option<&PathBuf>) -> std::io::Result<PathBuf> {
     if let Some(path) = db_path_override {
         if let Some(parent) = path.parent() {
@@ -0,0 +1,77 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+
+/// Converts a time to nanoseconds since the Unix epoch, the precision times are stored with.
+/// Times before the epoch, or too far after it to be represented, have no value.
+pub fn to_nanos(time: SystemTime) -> Option<i64> {
+    let duration = time.duration_since(UNIX_EPOCH).ok()?;
+    i64::try_from(duration.as_nanos()).ok()
+}
+
+/// Converts a time to nanoseconds since the Unix epoch, clamped to the range that can be
+/// represented, for times that must have a value (e.g. the bogus date of a Git commit)
+pub fn clamp_nanos(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX),
+        Err(_) => 0,
+    }
+}
+
+/// Converts nanoseconds since the Unix epoch to a time
+pub fn from_nanos(nanos: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_nanos(nanos.max(0) as u64)
+}
+
+/// Parses a date (`YYYY-MM-DD`) or date and time (`YYYY-MM-DDTHH:MM[:SS]`, a space is also
+/// accepted as separator) in local time, into nanoseconds since the Unix epoch
+pub fn parse_timestamp(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let datetime = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .or_else(|| {
+            [
+                "%Y-%m-%dT%H:%M:%S",
+                "%Y-%m-%dT%H:%M",
+                "%Y-%m-%d %H:%M:%S",
+                "%Y-%m-%d %H:%M",
+            ]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        })?;
+    Local
+        .from_local_datetime(&datetime)
+        .earliest()
+        .and_then(|t| t.timestamp_nanos_opt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let midnight = parse_timestamp("2025-03-01").unwrap();
+        assert_eq!(parse_timestamp("2025-03-01T00:00:00"), Some(midnight));
+        assert_eq!(
+            parse_timestamp("2025-03-01 12:30"),
+            Some(midnight + 45_000 * 1_000_000_000)
+        );
+        assert_eq!(parse_timestamp("2025-02-30"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn test_nanos_round_trip() {
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        assert_eq!(to_nanos(time), Some(1_700_000_000_123_456_789));
+        assert_eq!(from_nanos(1_700_000_000_123_456_789), time);
+        assert_eq!(to_nanos(UNIX_EPOCH - Duration::from_secs(1)), None);
+        assert_eq!(clamp_nanos(UNIX_EPOCH - Duration::from_secs(1)), 0);
+        assert_eq!(
+            clamp_nanos(UNIX_EPOCH + Duration::from_secs(u64::MAX / 2)),
+            i64::MAX
+        );
+    }
+}
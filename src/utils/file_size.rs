@@ -54,6 +54,44 @@ pub fn format_size_with_unit(bytes: u64, unit: &str) -> Result<String, String> {
     }
 }
 
+/// Parses a human-readable size such as `512`, `10KB`, `1.5 GiB` into a byte count.
+///
+/// Decimal units (KB, MB, GB, TB) are powers of 1000, binary units (KiB, MiB, GiB, TiB)
+/// powers of 1024. Units are case-insensitive.
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid size: {text}"))?;
+    let multiplier = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => B,
+        "k" | "kb" => 1000,
+        "m" | "mb" => 1000 * 1000,
+        "g" | "gb" => 1000 * 1000 * 1000,
+        "t" | "tb" => 1000 * 1000 * 1000 * 1000,
+        "kib" => KIB,
+        "mib" => MIB,
+        "gib" => GIB,
+        "tib" => TIB,
+        _ => return Err(format!("Invalid size unit: {unit}")),
+    };
+    Ok((number * multiplier as f64).round() as u64)
+}
+
+/// Parses a human-readable rate such as `50MiB/s` or `10MB` into a positive number of bytes per second
+pub fn parse_rate(text: &str) -> Result<u64, String> {
+    let size = text.trim();
+    let size = size.strip_suffix("/s").unwrap_or(size);
+    match parse_size(size)? {
+        0 => Err(format!("Invalid rate: {text}")),
+        rate => Ok(rate),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,4 +127,22 @@ mod tests {
             "1024.00 TiB"
         );
     }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("10KB").unwrap(), 10_000);
+        assert_eq!(parse_size("1.5 GiB").unwrap(), 3 * 512 * MIB);
+        assert_eq!(parse_size("2mib").unwrap(), 2 * MIB);
+        assert!(parse_size("1 parsec").is_err());
+        assert!(parse_size("GiB").is_err());
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("50MiB/s").unwrap(), 50 * MIB);
+        assert_eq!(parse_rate("1000").unwrap(), 1000);
+        assert!(parse_rate("0/s").is_err());
+        assert!(parse_rate("fast").is_err());
+    }
 }
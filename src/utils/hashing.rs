@@ -1,34 +1,448 @@
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-use blake3::Hasher;
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use xxhash_rust::xxh3::Xxh3;
+
+/// The algorithms available to hash file contents.
+///
+/// The algorithm is recorded per snapshot: hashes computed with different
+/// algorithms can't be compared with each other.
+#[derive(
+    ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// BLAKE3, fast and cryptographically secure (default)
+    #[default]
+    Blake3,
+    /// XXH3 (128 bits), much faster but not cryptographic
+    Xxh3,
+    /// SHA-256, for compatibility with existing checksum manifests
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /// Every supported algorithm
+    pub const ALL: [HashAlgorithm; 3] = [
+        HashAlgorithm::Blake3,
+        HashAlgorithm::Xxh3,
+        HashAlgorithm::Sha256,
+    ];
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashAlgorithm::Blake3 => write!(f, "blake3"),
+            HashAlgorithm::Xxh3 => write!(f, "xxh3"),
+            HashAlgorithm::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        HashAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown hash algorithm: {s}"))
+    }
+}
+
+/// Whether and how file contents are hashed when scanning
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum HashMode {
+    /// Every file is hashed (default)
+    #[default]
+    Full,
+    /// Files larger than `above` bytes are only sampled, see [`hash_file_sampled`]
+    Sampled { above: u64 },
+    /// No file is hashed, changes are detected from sizes and modification times
+    Metadata,
+}
+
+impl HashMode {
+    /// Whether a file of the given size is sampled rather than hashed in full
+    pub fn samples(&self, bytes: u64) -> bool {
+        matches!(self, HashMode::Sampled { above } if bytes > *above && bytes > 3 * SAMPLE_SIZE)
+    }
+
+    /// Number of bytes read to hash a file of the given size
+    pub fn bytes_read(&self, bytes: u64) -> u64 {
+        match self {
+            HashMode::Metadata => 0,
+            _ if self.samples(bytes) => 3 * SAMPLE_SIZE,
+            _ => bytes,
+        }
+    }
+}
+
+impl std::fmt::Display for HashMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashMode::Full => write!(f, "full"),
+            HashMode::Sampled { above } => write!(f, "sampled:{above}"),
+            HashMode::Metadata => write!(f, "metadata"),
+        }
+    }
+}
+
+impl std::str::FromStr for HashMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        match lower.split_once(':') {
+            None if lower == "full" => Ok(HashMode::Full),
+            None if lower == "metadata" => Ok(HashMode::Metadata),
+            Some(("sampled", above)) => above
+                .parse()
+                .map(|above| HashMode::Sampled { above })
+                .map_err(|_| format!("Invalid sampling threshold: {above}")),
+            _ => Err(format!("Unknown hash mode: {s}")),
+        }
+    }
+}
+
+impl TryFrom<String> for HashMode {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<HashMode> for String {
+    fn from(mode: HashMode) -> Self {
+        mode.to_string()
+    }
+}
+
+/// An incremental hasher for any of the supported algorithms
+pub enum ContentHasher {
+    Blake3(Box<blake3::Hasher>),
+    Xxh3(Box<Xxh3>),
+    Sha256(Sha256),
+}
+
+impl ContentHasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Blake3 => ContentHasher::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgorithm::Xxh3 => ContentHasher::Xxh3(Box::new(Xxh3::new())),
+            HashAlgorithm::Sha256 => ContentHasher::Sha256(Sha256::new()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            ContentHasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            ContentHasher::Xxh3(hasher) => hasher.update(data),
+            ContentHasher::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Completes the hash, returned as a lowercase hex string
+    pub fn finalize(self) -> String {
+        match self {
+            ContentHasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+            ContentHasher::Xxh3(hasher) => format!("{:032x}", hasher.digest128()),
+            ContentHasher::Sha256(hasher) => hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+        }
+    }
+}
 
 /// Compute a BLAKE3 hash of a byte slice, returned as a hex string
 pub fn hash_content(content: &[u8]) -> String {
-    let mut hasher = Hasher::new();
+    let mut hasher = ContentHasher::new(HashAlgorithm::Blake3);
     hasher.update(content);
-    hasher.finalize().to_hex().to_string()
+    hasher.finalize()
 }
 
 /// Buffer size for reading files in bytes
 const BUFFER_SIZE: usize = 8192;
 
-/// Compute a BLAKE3 hash of a file, returned as a hex string
-pub fn hash_file(path: &PathBuf) -> Result<String, std::io::Error> {
-    let mut file = File::open(path)?;
-    let mut hasher = Hasher::new();
+/// A token bucket limiting the rate at which file contents are read.
+///
+/// The bucket holds up to [`Throttle::BURST`] worth of reads. Reads beyond it put the bucket
+/// in debt, which is paid back by sleeping before the next read.
+#[derive(Debug)]
+pub struct Throttle {
+    /// Maximum rate in bytes per second, unlimited if `None`
+    rate: Option<u64>,
+    /// Bytes that may be read right away, negative when reads are ahead of the rate
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Throttle {
+    /// How long reads may run at full speed after an idle period
+    const BURST: Duration = Duration::from_millis(100);
+
+    pub fn new(rate: Option<u64>) -> Self {
+        let mut throttle = Throttle {
+            rate,
+            tokens: 0.0,
+            last_refill: Instant::now(),
+        };
+        throttle.tokens = throttle.capacity();
+        throttle
+    }
+
+    pub fn unlimited() -> Self {
+        Throttle::new(None)
+    }
+
+    fn capacity(&self) -> f64 {
+        self.rate.unwrap_or_default() as f64 * Self::BURST.as_secs_f64()
+    }
+
+    /// Records that `bytes` are about to be read, waiting as long as needed to respect the rate
+    pub fn consume(&mut self, bytes: u64) {
+        let Some(rate) = self.rate else {
+            return;
+        };
+        let now = Instant::now();
+        let refill = now.duration_since(self.last_refill).as_secs_f64() * rate as f64;
+        self.tokens = (self.tokens + refill).min(self.capacity()) - bytes as f64;
+        self.last_refill = now;
+        if self.tokens < 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(-self.tokens / rate as f64));
+        }
+    }
+}
+
+/// The hash of the content of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHash {
+    /// The hash, as a lowercase hex string
+    pub hash: String,
+    /// Whether reading the file left its access time untouched
+    pub atime_preserved: bool,
+}
+
+/// A file opened for hashing, read without disturbing its access time where possible.
+///
+/// On Linux the file is opened with `O_NOATIME`, which is only permitted to the owner of the
+/// file. Otherwise the access time is read before the file is, and restored by [`Self::close`].
+struct HashedFile {
+    file: File,
+    /// The access time to restore, if the file could not be opened with `O_NOATIME`
+    atime: Option<std::time::SystemTime>,
+}
+
+impl HashedFile {
+    fn open(path: &PathBuf) -> Result<Self, std::io::Error> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            match File::options()
+                .read(true)
+                .custom_flags(libc::O_NOATIME)
+                .open(path)
+            {
+                Ok(file) => return Ok(HashedFile { file, atime: None }),
+                Err(e) if e.raw_os_error() == Some(libc::EPERM) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let file = File::open(path)?;
+        let atime = file.metadata()?.accessed().ok();
+        Ok(HashedFile { file, atime })
+    }
+
+    /// Restores the access time if needed. Returns whether the access time was preserved.
+    fn close(self) -> bool {
+        match self.atime {
+            None => true,
+            Some(atime) => self
+                .file
+                .set_times(std::fs::FileTimes::new().set_accessed(atime))
+                .is_ok(),
+        }
+    }
+}
+
+/// Compute the hash of a file with the given algorithm, without disturbing its access time where possible.
+///
+/// Reads are paced by `throttle`.
+pub fn hash_file(
+    path: &PathBuf,
+    algorithm: HashAlgorithm,
+    throttle: &mut Throttle,
+) -> Result<FileHash, std::io::Error> {
+    let mut file = HashedFile::open(path)?;
+    let mut hasher = ContentHasher::new(algorithm);
     let mut buffer = [0u8; BUFFER_SIZE];
 
     // Read the file in chunks and update the hasher
     loop {
-        let n = file.read(&mut buffer)?;
+        throttle.consume(BUFFER_SIZE as u64);
+        let n = file.file.read(&mut buffer)?;
         if n == 0 {
             break;
         }
         hasher.update(&buffer[..n]);
     }
 
-    // Finalize the hash and return as a hex string
-    Ok(hasher.finalize().to_hex().to_string())
+    Ok(FileHash {
+        hash: hasher.finalize(),
+        atime_preserved: file.close(),
+    })
+}
+
+/// Size of each of the samples read from large files
+pub const SAMPLE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Compute a partial hash of a large file, without disturbing its access time where possible.
+///
+/// Only the size and the first, middle and last [`SAMPLE_SIZE`] bytes of the file are hashed,
+/// so the hash identifies the content much less reliably than a full hash: edits outside the
+/// samples that keep the size go unnoticed. Files too small to sample are hashed in full.
+pub fn hash_file_sampled(
+    path: &PathBuf,
+    algorithm: HashAlgorithm,
+    throttle: &mut Throttle,
+) -> Result<FileHash, std::io::Error> {
+    let mut file = HashedFile::open(path)?;
+    let len = file.file.metadata()?.len();
+    if len <= 3 * SAMPLE_SIZE {
+        drop(file);
+        return hash_file(path, algorithm, throttle);
+    }
+
+    let mut hasher = ContentHasher::new(algorithm);
+    hasher.update(&len.to_le_bytes());
+    let mut buffer = vec![0u8; SAMPLE_SIZE as usize];
+    for offset in [0, (len - SAMPLE_SIZE) / 2, len - SAMPLE_SIZE] {
+        throttle.consume(SAMPLE_SIZE);
+        file.file.seek(SeekFrom::Start(offset))?;
+        file.file.read_exact(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+
+    Ok(FileHash {
+        hash: hasher.finalize(),
+        atime_preserved: file.close(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_hashes() {
+        let hash = |algorithm| {
+            let mut hasher = ContentHasher::new(algorithm);
+            hasher.update(b"abc");
+            hasher.finalize()
+        };
+        assert_eq!(
+            hash(HashAlgorithm::Sha256),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hash(HashAlgorithm::Blake3),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        assert_eq!(hash(HashAlgorithm::Xxh3).len(), 32);
+    }
+
+    #[test]
+    fn test_algorithm_names_round_trip() {
+        for algorithm in HashAlgorithm::ALL {
+            assert_eq!(algorithm.to_string().parse(), Ok(algorithm));
+        }
+        assert!("md5".parse::<HashAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_hash_mode_names_round_trip() {
+        for mode in [
+            HashMode::Full,
+            HashMode::Sampled { above: 1 << 30 },
+            HashMode::Metadata,
+        ] {
+            assert_eq!(mode.to_string().parse(), Ok(mode));
+        }
+        assert!("sampled:big".parse::<HashMode>().is_err());
+    }
+
+    #[test]
+    fn test_sampled_hash_ignores_unsampled_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image.bin");
+        let sample = |path: &PathBuf| {
+            hash_file_sampled(path, HashAlgorithm::Blake3, &mut Throttle::unlimited())
+                .unwrap()
+                .hash
+        };
+        let len = 4 * SAMPLE_SIZE as usize;
+        let mut content = vec![0u8; len];
+        std::fs::write(&path, &content).unwrap();
+        let sampled = sample(&path);
+        let full = hash_file(&path, HashAlgorithm::Blake3, &mut Throttle::unlimited())
+            .unwrap()
+            .hash;
+        assert_ne!(sampled, full);
+
+        // Between the first and the middle sample
+        content[SAMPLE_SIZE as usize + 1] = 1;
+        std::fs::write(&path, &content).unwrap();
+        assert_eq!(sample(&path), sampled);
+
+        // In the last sample
+        content[len - 1] = 1;
+        std::fs::write(&path, &content).unwrap();
+        assert_ne!(sample(&path), sampled);
+    }
+
+    #[test]
+    fn test_hash_file_preserves_atime() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        std::fs::write(&path, b"abc").unwrap();
+        let atime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_times(std::fs::FileTimes::new().set_accessed(atime))
+            .unwrap();
+
+        let hashed = hash_file(&path, HashAlgorithm::Sha256, &mut Throttle::unlimited()).unwrap();
+        assert!(hashed.atime_preserved);
+        assert_eq!(std::fs::metadata(&path).unwrap().accessed().unwrap(), atime);
+    }
+
+    #[test]
+    fn test_throttle_limits_rate() {
+        let mut throttle = Throttle::new(Some(1_000_000));
+        let started = Instant::now();
+        // The first 100 kB are the burst, the next 200 kB take 200 ms
+        for _ in 0..3 {
+            throttle.consume(100_000);
+        }
+        assert!(started.elapsed() >= Duration::from_millis(150));
+
+        let mut throttle = Throttle::unlimited();
+        let started = Instant::now();
+        throttle.consume(u64::MAX);
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
 }
@@ -0,0 +1,106 @@
+use std::io::Write;
+
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Defines the possible formats for log messages
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    Text, // Default format
+    Json,
+}
+
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// A minimal logger that writes diagnostic messages to stderr.
+///
+/// Stdout is reserved for the data output of the commands, so every log record
+/// (regardless of level) goes to stderr, either as plain text or as one JSON
+/// object per line.
+struct Logger {
+    level: LevelFilter,
+    format: LogFormat,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = match self.format {
+            LogFormat::Text => match record.level() {
+                Level::Info => format!("{}", record.args()),
+                level => format!("{}: {}", level.as_str().to_lowercase(), record.args()),
+            },
+            LogFormat::Json => serde_json::json!({
+                "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            })
+            .to_string(),
+        };
+
+        // Ignore write failures, there is nowhere left to report them
+        let _ = writeln!(std::io::stderr().lock(), "{line}");
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// Determines the log level from the `-q` and `-v` command-line flags.
+///
+/// | Flags   | Level   |
+/// |---------|---------|
+/// | `-q`    | `error` |
+/// | (none)  | `info`  |
+/// | `-v`    | `debug` |
+/// | `-vv`   | `trace` |
+pub fn level_from_verbosity(quiet: bool, verbose: u8) -> LevelFilter {
+    if quiet {
+        return LevelFilter::Error;
+    }
+    match verbose {
+        0 => LevelFilter::Info,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Installs the global logger. Should be called once, before any command runs.
+pub fn init(level: LevelFilter, format: LogFormat) {
+    let logger = Logger { level, format };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_from_verbosity() {
+        assert_eq!(level_from_verbosity(true, 0), LevelFilter::Error);
+        assert_eq!(level_from_verbosity(true, 2), LevelFilter::Error);
+        assert_eq!(level_from_verbosity(false, 0), LevelFilter::Info);
+        assert_eq!(level_from_verbosity(false, 1), LevelFilter::Debug);
+        assert_eq!(level_from_verbosity(false, 2), LevelFilter::Trace);
+        assert_eq!(level_from_verbosity(false, 5), LevelFilter::Trace);
+    }
+}
@@ -1,7 +1,19 @@
 mod data_dir;
 pub use data_dir::*;
 
+mod datetime;
+pub use datetime::*;
+
+mod paths;
+pub use paths::*;
+
 mod file_size;
 pub use file_size::*;
 
+mod prompt;
+pub use prompt::*;
+
 pub mod hashing;
+pub mod logger;
+pub mod priority;
+pub mod progress;
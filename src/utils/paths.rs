@@ -0,0 +1,258 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::de::Error as _;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use unicode_normalization::{UnicodeNormalization, is_nfc};
+
+/// The raw bytes of a path, as they are stored in the database
+pub fn path_to_bytes(path: &Path) -> &[u8] {
+    path.as_os_str().as_encoded_bytes()
+}
+
+/// Restores a path from the raw bytes it was stored as
+#[cfg(unix)]
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+/// Restores a path from the raw bytes it was stored as. Outside of Unix, only paths that are
+/// valid Unicode round-trip; the others are decoded lossily.
+#[cfg(not(unix))]
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Decodes bytes as UTF-8, escaping each byte that isn't valid as `\xNN`
+pub fn escape_bytes(bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(bytes.len() + 8);
+    for chunk in bytes.utf8_chunks() {
+        escaped.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            let _ = write!(escaped, "\\x{byte:02x}");
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Displays a path in text output, escaping the bytes that aren't valid UTF-8 as `\xNN`
+pub fn display_path(path: &Path) -> Cow<'_, str> {
+    escape_bytes(path_to_bytes(path))
+}
+
+/// How a path is represented in JSON: a string if it is valid UTF-8, its raw bytes in hex otherwise
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonPath {
+    Text(String),
+    Bytes { hex: String },
+}
+
+/// Serializes a path as a string, or as `{"hex": "..."}` with its raw bytes if it isn't
+/// valid UTF-8, so that such paths can't be confused with any string
+pub fn serialize_path<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    match path.to_str() {
+        Some(text) => serializer.serialize_str(text),
+        None => {
+            let hex = path_to_bytes(path)
+                .iter()
+                .fold(String::new(), |mut hex, byte| {
+                    let _ = write!(hex, "{byte:02x}");
+                    hex
+                });
+            let mut map = serializer.serialize_map(Some(1))?;
+            map.serialize_entry("hex", &hex)?;
+            map.end()
+        }
+    }
+}
+
+/// Serializes a list of paths like [`serialize_path`]
+pub fn serialize_paths<S: Serializer>(paths: &[PathBuf], serializer: S) -> Result<S::Ok, S::Error> {
+    struct Item<'a>(&'a Path);
+
+    impl Serialize for Item<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_path(self.0, serializer)
+        }
+    }
+
+    serializer.collect_seq(paths.iter().map(|path| Item(path)))
+}
+
+/// Deserializes a path written by [`serialize_path`]
+pub fn deserialize_path<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    match JsonPath::deserialize(deserializer)? {
+        JsonPath::Text(text) => Ok(PathBuf::from(text)),
+        JsonPath::Bytes { hex } => {
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|i| {
+                    hex.get(i..i + 2)
+                        .and_then(|b| u8::from_str_radix(b, 16).ok())
+                })
+                .collect::<Option<Vec<u8>>>()
+                .ok_or_else(|| D::Error::custom(format!("invalid hex path '{hex}'")))?;
+            Ok(path_from_bytes(bytes))
+        }
+    }
+}
+
+/// How paths are matched when comparing snapshots
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PathNormalization {
+    /// Paths match byte for byte (default)
+    #[default]
+    Exact,
+    /// Paths match when they are the same in Unicode normalization form C, e.g. names written
+    /// decomposed (NFD) by macOS and precomposed elsewhere
+    Nfc,
+    /// Paths match when they are the same in NFC after Unicode case folding, like on
+    /// case-insensitive filesystems
+    Casefold,
+}
+
+impl std::fmt::Display for PathNormalization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathNormalization::Exact => write!(f, "exact"),
+            PathNormalization::Nfc => write!(f, "nfc"),
+            PathNormalization::Casefold => write!(f, "casefold"),
+        }
+    }
+}
+
+impl PathNormalization {
+    /// The key a path is matched by. Paths that aren't valid UTF-8 are matched exactly.
+    pub fn key<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        let Some(text) = path.to_str() else {
+            return Cow::Borrowed(path);
+        };
+        match self {
+            PathNormalization::Exact => Cow::Borrowed(path),
+            PathNormalization::Nfc if is_nfc(text) => Cow::Borrowed(path),
+            PathNormalization::Nfc => Cow::Owned(PathBuf::from(text.nfc().collect::<String>())),
+            PathNormalization::Casefold => {
+                let folded = caseless::default_case_fold_str(&text.nfd().collect::<String>());
+                Cow::Owned(PathBuf::from(folded.nfc().collect::<String>()))
+            }
+        }
+    }
+
+    /// Indexes items by the key of their path.
+    ///
+    /// Paths that collide with another one under this normalization are indexed by their exact
+    /// path instead, so that no item is lost; they are logged as a warning.
+    pub fn index<'a, T>(
+        &self,
+        items: &'a [T],
+        path: impl Fn(&'a T) -> &'a Path,
+    ) -> HashMap<Cow<'a, Path>, &'a T> {
+        let mut groups: HashMap<Cow<'a, Path>, Vec<&'a T>> = HashMap::new();
+        for item in items {
+            groups.entry(self.key(path(item))).or_default().push(item);
+        }
+
+        let mut index = HashMap::with_capacity(items.len());
+        let mut colliding = 0;
+        for (key, group) in groups {
+            if let [item] = group[..] {
+                index.insert(key, item);
+                continue;
+            }
+            colliding += group.len();
+            for item in group {
+                index.insert(Cow::Borrowed(path(item)), item);
+            }
+        }
+        if colliding > 0 {
+            log::warn!(
+                "{colliding} paths collide under {self} normalization, they are matched exactly"
+            );
+        }
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_bytes() {
+        assert_eq!(escape_bytes(b"caf\xc3\xa9.txt"), "café.txt");
+        assert_eq!(escape_bytes(b"bad\xff\xfe.txt"), "bad\\xff\\xfe.txt");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_round_trip() {
+        #[derive(Serialize, Deserialize)]
+        struct File {
+            #[serde(
+                serialize_with = "serialize_path",
+                deserialize_with = "deserialize_path"
+            )]
+            path: PathBuf,
+        }
+
+        let path = path_from_bytes(b"dir/bad\xff.txt".to_vec());
+        assert_eq!(path_to_bytes(&path), b"dir/bad\xff.txt");
+        assert_eq!(display_path(&path), "dir/bad\\xff.txt");
+
+        let json = serde_json::to_string(&File { path: path.clone() }).unwrap();
+        assert_eq!(json, r#"{"path":{"hex":"6469722f626164ff2e747874"}}"#);
+        let file: File = serde_json::from_str(&json).unwrap();
+        assert_eq!(file.path, path);
+
+        let file: File = serde_json::from_str(r#"{"path":"dir/a.txt"}"#).unwrap();
+        assert_eq!(file.path, PathBuf::from("dir/a.txt"));
+    }
+
+    #[test]
+    fn test_path_normalization() {
+        let nfd = Path::new("Cafe\u{301}/Stra\u{df}e.txt");
+        let nfc = Path::new("Caf\u{e9}/Stra\u{df}e.txt");
+        let upper = Path::new("CAF\u{c9}/STRASSE.TXT");
+
+        assert_ne!(
+            PathNormalization::Exact.key(nfd),
+            PathNormalization::Exact.key(nfc)
+        );
+        assert_eq!(
+            PathNormalization::Nfc.key(nfd),
+            PathNormalization::Nfc.key(nfc)
+        );
+        assert_ne!(
+            PathNormalization::Nfc.key(nfc),
+            PathNormalization::Nfc.key(upper)
+        );
+        assert_eq!(
+            PathNormalization::Casefold.key(nfd),
+            PathNormalization::Casefold.key(upper)
+        );
+    }
+
+    #[test]
+    fn test_index_keeps_colliding_paths() {
+        let paths = [
+            PathBuf::from("a.txt"),
+            PathBuf::from("A.txt"),
+            PathBuf::from("b.txt"),
+        ];
+        let index = PathNormalization::Casefold.index(&paths, |path| path.as_path());
+        assert_eq!(index.len(), 3);
+        assert!(index.contains_key(Path::new("a.txt")));
+        assert!(index.contains_key(Path::new("A.txt")));
+        assert!(index.contains_key(Path::new("b.txt")));
+    }
+}
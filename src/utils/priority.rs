@@ -0,0 +1,95 @@
+use clap::ValueEnum;
+
+/// The I/O scheduling classes a scan can be lowered to
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IoClass {
+    /// Lowest priority of the default best-effort class
+    BestEffort,
+    /// Only use the disk when no other process needs it
+    Idle,
+}
+
+impl std::fmt::Display for IoClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IoClass::BestEffort => write!(f, "best-effort"),
+            IoClass::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+/// The valid niceness values, from the highest priority to the lowest
+pub const NICE_RANGE: std::ops::RangeInclusive<i32> = -20..=19;
+
+/// Parses a niceness, checking that it is in [`NICE_RANGE`]
+pub fn parse_nice(s: &str) -> Result<i32, String> {
+    let nice = s
+        .parse()
+        .map_err(|e| format!("Invalid niceness '{s}': {e}"))?;
+    check_nice(nice)
+}
+
+/// Checks that a niceness is in [`NICE_RANGE`]
+pub fn check_nice(nice: i32) -> Result<i32, String> {
+    if NICE_RANGE.contains(&nice) {
+        Ok(nice)
+    } else {
+        Err(format!("Niceness {nice} is not between -20 and 19"))
+    }
+}
+
+/// Sets the niceness of the calling thread to the given value. The value is absolute, not
+/// relative to the current niceness.
+///
+/// On Linux the niceness applies to the calling thread only, so that concurrent scans can run
+/// at different priorities. Unprivileged processes can only raise the niceness: they can't get
+/// their priority back afterwards, and negative values require privileges.
+pub fn set_nice(nice: i32) -> std::io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: setpriority has no memory safety requirements
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = nice;
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "setting the niceness is only supported on Linux",
+        ))
+    }
+}
+
+/// Sets the I/O scheduling class of the calling thread
+pub fn set_io_class(class: IoClass) -> std::io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        // See ioprio_set(2)
+        const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+        const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+        const IOPRIO_CLASS_BE: libc::c_int = 2;
+        const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+
+        let priority = match class {
+            IoClass::BestEffort => (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | 7,
+            IoClass::Idle => IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        };
+        // SAFETY: ioprio_set only takes integer arguments
+        if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, priority) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = class;
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "setting the I/O scheduling class is only supported on Linux",
+        ))
+    }
+}
@@ -0,0 +1,260 @@
+use std::io::{IsTerminal, Write};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+use crate::utils::format_size_auto;
+
+/// Defines how progress of long-running operations is reported
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// Live display on a terminal, NDJSON events otherwise
+    Auto,
+    /// Always draw the live display
+    Bar,
+    /// Always emit NDJSON progress events
+    Ndjson,
+    /// Never report progress
+    Never,
+}
+
+impl std::fmt::Display for ProgressMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgressMode::Auto => write!(f, "auto"),
+            ProgressMode::Bar => write!(f, "bar"),
+            ProgressMode::Ndjson => write!(f, "ndjson"),
+            ProgressMode::Never => write!(f, "never"),
+        }
+    }
+}
+
+/// The resolved progress output, after checking whether stderr is a terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Output {
+    Bar,
+    Ndjson,
+    Hidden,
+}
+
+static OUTPUT: OnceLock<Output> = OnceLock::new();
+
+/// Whether progress reporting was turned off after `init`, see [`disable`]
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// How often the live display is redrawn
+const BAR_INTERVAL: Duration = Duration::from_millis(100);
+/// How often NDJSON progress events are emitted
+const NDJSON_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configures progress reporting for the process. Should be called once, before any command runs.
+///
+/// Progress is never reported in quiet mode.
+pub fn init(mode: ProgressMode, quiet: bool) {
+    let output = match mode {
+        _ if quiet => Output::Hidden,
+        ProgressMode::Auto if std::io::stderr().is_terminal() => Output::Bar,
+        ProgressMode::Auto => Output::Ndjson,
+        ProgressMode::Bar => Output::Bar,
+        ProgressMode::Ndjson => Output::Ndjson,
+        ProgressMode::Never => Output::Hidden,
+    };
+    let _ = OUTPUT.set(output);
+}
+
+/// Stops reporting progress for the rest of the process, e.g. because concurrent operations
+/// would interleave their output
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Tracks and reports the progress of a single phase of work (e.g. walking or hashing).
+///
+/// Progress is written to stderr, either as a live single-line display or as
+/// periodic newline-delimited JSON events for GUIs and wrappers to consume.
+pub struct Progress {
+    /// Name of the phase being reported (e.g. `walk`, `hash`, `git`)
+    phase: &'static str,
+    /// What is being counted (e.g. `files`, `commits`)
+    unit: &'static str,
+    output: Output,
+    total_items: Option<u64>,
+    total_bytes: Option<u64>,
+    items: u64,
+    bytes: u64,
+    started: Instant,
+    last_report: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(phase: &'static str, unit: &'static str) -> Self {
+        Progress {
+            phase,
+            unit,
+            output: match OUTPUT.get() {
+                Some(&output) if !DISABLED.load(Ordering::Relaxed) => output,
+                _ => Output::Hidden,
+            },
+            total_items: None,
+            total_bytes: None,
+            items: 0,
+            bytes: 0,
+            started: Instant::now(),
+            last_report: None,
+        }
+    }
+
+    /// Sets the expected number of items, enabling the `N/M` display and the ETA
+    pub fn with_total_items(mut self, total: u64) -> Self {
+        self.total_items = Some(total);
+        self
+    }
+
+    /// Sets the expected number of bytes, enabling a byte-based ETA
+    pub fn with_total_bytes(mut self, total: u64) -> Self {
+        self.total_bytes = Some(total);
+        self
+    }
+
+    /// Records that `items` more items and `bytes` more bytes have been processed
+    pub fn inc(&mut self, items: u64, bytes: u64) {
+        self.items += items;
+        self.bytes += bytes;
+
+        let interval = match self.output {
+            Output::Hidden => return,
+            Output::Bar => BAR_INTERVAL,
+            Output::Ndjson => NDJSON_INTERVAL,
+        };
+        let now = Instant::now();
+        if self
+            .last_report
+            .is_some_and(|last| now.duration_since(last) < interval)
+        {
+            return;
+        }
+        self.last_report = Some(now);
+        self.report("progress");
+    }
+
+    /// Completes the phase, clearing the live display or emitting a final event
+    pub fn finish(self) {
+        match self.output {
+            Output::Hidden => {}
+            Output::Bar => {
+                let _ = write!(std::io::stderr().lock(), "\r\x1b[2K");
+            }
+            Output::Ndjson => self.report("done"),
+        }
+    }
+
+    /// Processing rate in bytes per second, or items per second if no bytes were counted
+    fn rate(&self, elapsed: Duration) -> f64 {
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        if self.bytes > 0 {
+            self.bytes as f64 / secs
+        } else {
+            self.items as f64 / secs
+        }
+    }
+
+    /// Estimated time remaining, if the totals are known
+    fn eta(&self, elapsed: Duration) -> Option<Duration> {
+        let secs = elapsed.as_secs_f64();
+        let fraction = match (self.total_bytes, self.total_items) {
+            (Some(total), _) if total > 0 => self.bytes as f64 / total as f64,
+            (_, Some(total)) if total > 0 => self.items as f64 / total as f64,
+            _ => return None,
+        };
+        if fraction <= 0.0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64((secs / fraction - secs).max(0.0)))
+    }
+
+    fn report(&self, event: &str) {
+        let elapsed = self.started.elapsed();
+        let rate = self.rate(elapsed);
+        let eta = self.eta(elapsed);
+        let mut stderr = std::io::stderr().lock();
+
+        match self.output {
+            Output::Hidden => {}
+            Output::Bar => {
+                let mut line = match self.total_items {
+                    Some(total) => format!("{}: {}/{total} {}", self.phase, self.items, self.unit),
+                    None => format!("{}: {} {}", self.phase, self.items, self.unit),
+                };
+                if self.bytes > 0 {
+                    line.push_str(&format!(
+                        ", {} ({}/s)",
+                        format_size_auto(self.bytes),
+                        format_size_auto(rate as u64)
+                    ));
+                } else {
+                    line.push_str(&format!(", {rate:.1} {}/s", self.unit));
+                }
+                if let Some(eta) = eta {
+                    line.push_str(&format!(", ETA {}", format_duration(eta)));
+                }
+                let _ = write!(stderr, "\r\x1b[2K{line}");
+                let _ = stderr.flush();
+            }
+            Output::Ndjson => {
+                let json = serde_json::json!({
+                    "event": event,
+                    "phase": self.phase,
+                    "unit": self.unit,
+                    "items": self.items,
+                    "total_items": self.total_items,
+                    "bytes": self.bytes,
+                    "total_bytes": self.total_bytes,
+                    "elapsed_ms": elapsed.as_millis() as u64,
+                    "rate": rate,
+                    "eta_secs": eta.map(|d| d.as_secs()),
+                });
+                let _ = writeln!(stderr, "{json}");
+            }
+        }
+    }
+}
+
+/// Formats a duration as `HH:MM:SS`, or `MM:SS` when shorter than an hour
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if h > 0 {
+        format!("{h:02}:{m:02}:{s:02}")
+    } else {
+        format!("{m:02}:{s:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "00:00");
+        assert_eq!(format_duration(Duration::from_secs(75)), "01:15");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "01:02:05");
+    }
+
+    #[test]
+    fn test_eta_from_bytes() {
+        let mut progress = Progress::new("hash", "files").with_total_bytes(100);
+        progress.inc(1, 25);
+        let eta = progress.eta(Duration::from_secs(10)).unwrap();
+        assert_eq!(eta.as_secs(), 30);
+    }
+
+    #[test]
+    fn test_eta_unknown_without_totals() {
+        let mut progress = Progress::new("walk", "files");
+        progress.inc(10, 0);
+        assert!(progress.eta(Duration::from_secs(10)).is_none());
+    }
+}
@@ -0,0 +1,21 @@
+use std::io::{BufRead, IsTerminal, Write};
+
+/// Asks the user a yes/no question on stderr and reads the answer from stdin.
+///
+/// Without an interactive terminal there is nobody to ask, so this fails instead of
+/// guessing; destructive commands offer `--yes` for scripted use.
+pub fn confirm(question: &str) -> std::io::Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        return Err(std::io::Error::other(
+            "Refusing to continue without confirmation, pass --yes to confirm",
+        ));
+    }
+
+    let mut stderr = std::io::stderr().lock();
+    write!(stderr, "{question} [y/N] ")?;
+    stderr.flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}